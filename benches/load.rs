@@ -0,0 +1,81 @@
+// Compares ways of loading a huge wordlist: the old loader, the single-pass one, and a prebuilt index.
+// Run with "cargo bench".  Uses words_alpha.txt (370k words) from the repository.
+// The modules are only partly used here, and their unit tests aren't run.
+#![allow(dead_code, unused_imports)]
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::{Duration, Instant};
+
+#[path = "../src/difficulty.rs"]
+mod difficulty;
+#[path = "../src/index.rs"]
+mod index;
+#[path = "../src/text.rs"]
+mod text;
+#[path = "../src/words.rs"]
+mod words;
+
+const RUNS: u32 = 5;
+
+// How the words were loaded before: every line into a Vec<String>, then one cloning pass per word length.
+fn old_loader(filename: &str) -> HashMap<u32, Vec<String>> {
+    let file = BufReader::new(File::open(filename).unwrap());
+    let words: Vec<String> = file
+        .lines()
+        .map(|l| l.unwrap().trim().to_string())
+        .collect();
+    let mut word_sets: HashMap<u32, Vec<String>> = HashMap::new();
+    for (ch, i) in (3usize..=11usize)
+        .collect::<Vec<usize>>()
+        .chunks(3)
+        .zip(1..=3)
+    {
+        let set: Vec<String> = ch
+            .iter()
+            .flat_map(|&n| words.iter().filter(move |w| text::length(w) == n).cloned())
+            .collect();
+        word_sets.insert(i, set);
+    }
+    let long: Vec<String> = words
+        .iter()
+        .filter(|w| text::length(w) >= 12)
+        .cloned()
+        .collect();
+    word_sets.insert(4, long);
+    word_sets
+}
+
+fn time<T, F: FnMut() -> T>(name: &str, mut run: F) {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        std::hint::black_box(run());
+        best = best.min(start.elapsed());
+    }
+    println!("{:<12} {:>8.1} ms", name, best.as_secs_f64() * 1000.);
+}
+
+fn main() {
+    let filename = concat!(env!("CARGO_MANIFEST_DIR"), "/words_alpha.txt");
+    let bytes = std::fs::read(filename).unwrap();
+    let mut words = words::parse(filename, &bytes);
+    let key = index::key(&[bytes], "bench");
+    words::assign_difficulties(&mut words);
+    // The index goes in the temp directory, not the game's, and is removed at the end.
+    let dir = std::env::temp_dir();
+    index::save(&dir, key, &words).unwrap();
+
+    println!("Best of {} runs loading {} words:", RUNS, words.len());
+    time("old", || old_loader(filename));
+    time("single pass", || {
+        let bytes = std::fs::read(filename).unwrap();
+        words::WordSets::new(words::parse(filename, &bytes), false)
+    });
+    time("index", || {
+        let bytes = std::fs::read(filename).unwrap();
+        let key = index::key(&[bytes], "bench");
+        words::WordSets::new(index::load(&dir, key).unwrap(), false)
+    });
+    std::fs::remove_file(index::path(&dir, key)).unwrap();
+}
@@ -0,0 +1,100 @@
+// Choosing the difficulty of each word queued up.
+// Difficulties are the buckets made by parse_to_sets(): 1 for 3-5 letters, up to 4 for 12 and over.
+use rand::Rng;
+use std::collections::VecDeque;
+use strum_macros::AsRefStr;
+
+use crate::text::length;
+
+// How many of the latest words are averaged for Progression::Speed.
+const RECENT: usize = 5;
+
+#[derive(PartialEq, Eq, Clone, Copy, AsRefStr)]
+pub enum Progression {
+    Fixed,  // Always the lowest difficulty.
+    Count,  // Up one difficulty every 20 words.
+    Speed,  // Follows your WPM over the last few words.
+    Random, // Anything in range.
+}
+
+impl Progression {
+    pub fn from_name(name: &str) -> Option<Progression> {
+        match name {
+            "fixed" => Some(Progression::Fixed),
+            "count" => Some(Progression::Count),
+            "speed" => Some(Progression::Speed),
+            "random" => Some(Progression::Random),
+            _ => None,
+        }
+    }
+}
+
+pub struct Ramp {
+    progression: Progression,
+    low: u32,
+    high: u32,
+    recent: VecDeque<f32>, // WPM of the latest words.
+}
+
+impl Ramp {
+    pub fn new(progression: Progression, low: u32, high: u32) -> Ramp {
+        Ramp {
+            progression,
+            low,
+            high,
+            recent: VecDeque::new(),
+        }
+    }
+
+    // Call after each correct word.
+    pub fn record(&mut self, chars: usize, secs: f32) {
+        if secs > 0. {
+            self.recent.push_back(12. * chars as f32 / secs);
+        }
+        if self.recent.len() > RECENT {
+            self.recent.pop_front();
+        }
+    }
+
+    // Difficulty for the next word, `words` words into the game.
+    pub fn next<R: Rng>(&self, words: u32, rng: &mut R) -> u32 {
+        match self.progression {
+            Progression::Random => rng.gen_range(self.low, self.high + 1),
+            _ => self.level(words),
+        }
+    }
+
+    // Difficulty the game has reached, `words` words in.  Random doesn't progress, so it's always at the top.
+    pub fn level(&self, words: u32) -> u32 {
+        let step = match self.progression {
+            Progression::Fixed => 0,
+            Progression::Count => words / 20,
+            Progression::Speed => match self.recent.len() {
+                0 => 0,
+                n => match self.recent.iter().sum::<f32>() / n as f32 {
+                    wpm if wpm < 30. => 0,
+                    wpm if wpm < 45. => 1,
+                    wpm if wpm < 60. => 2,
+                    _ => 3,
+                },
+            },
+            Progression::Random => self.high - self.low,
+        };
+        (self.low + step).min(self.high)
+    }
+}
+
+// Difficulty bucket a word belongs in, by its length in letters.
+pub fn bucket_of(word: &str) -> u32 {
+    bucket_for(length(word))
+}
+
+// Difficulty bucket for a word of `letters` letters.
+pub fn bucket_for(letters: usize) -> u32 {
+    match letters {
+        0..=5 => 1,
+        6..=8 => 2,
+        9..=11 => 3,
+        _ => 4,
+    }
+}
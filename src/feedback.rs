@@ -0,0 +1,150 @@
+// What the player is told after typing a word wrong: where the word went wrong, and a message
+// picked at random so mistakes don't all look the same.
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fs;
+use std::io;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::text::Matching;
+
+const MESSAGES: &[&str] = &[
+    "Not quite.",
+    "Close, but no.",
+    "Oops!",
+    "Try that one again.",
+    "So close!",
+    "Careful now.",
+    "Slow is smooth, smooth is fast.",
+    "Almost had it.",
+];
+
+pub struct Messages {
+    messages: Vec<String>,
+}
+
+impl Messages {
+    // Messages from a file, one per line, or the built-in ones if `path` is None.
+    // Blank lines and lines starting with '#' are skipped.
+    pub fn load(path: Option<&str>) -> io::Result<Messages> {
+        let messages: Vec<String> = match path {
+            Some(path) => fs::read_to_string(path)?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from)
+                .collect(),
+            None => MESSAGES.iter().map(|message| message.to_string()).collect(),
+        };
+        if messages.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("No messages in {}.", path.unwrap_or("the message file")),
+            ));
+        }
+        Ok(Messages { messages })
+    }
+
+    pub fn pick<R: Rng>(&self, rng: &mut R) -> &str {
+        self.messages
+            .choose(rng)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+// How one letter of the typed word lines up with the target.
+enum Step<'a> {
+    Same(&'a str),
+    Wrong(&'a str),   // Typed in place of a different letter.
+    Extra(&'a str),   // Typed, but not in the target.
+    Missing(&'a str), // In the target, but not typed.
+}
+
+// The typed word with its mistakes marked: wrong and extra letters in red, and missing letters
+// from the target, dimmed, where they should have been.  Letters are lined up by edit distance,
+// so one missed letter doesn't mark the rest of the word wrong.
+pub fn diff(typed: &str, target: &str, matching: &Matching) -> String {
+    let typed: Vec<&str> = typed.graphemes(true).collect();
+    let target: Vec<&str> = target.graphemes(true).collect();
+    let (rows, columns) = (typed.len() + 1, target.len() + 1);
+
+    // cost[i][j] is the edit distance between the first i typed letters and the first j target letters.
+    let mut cost = vec![vec![0usize; columns]; rows];
+    for (i, row) in cost.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    cost[0] = (0..columns).collect();
+    for i in 1..rows {
+        for j in 1..columns {
+            let change = !matching.matches(typed[i - 1], target[j - 1]) as usize;
+            cost[i][j] = (cost[i - 1][j - 1] + change)
+                .min(cost[i - 1][j] + 1)
+                .min(cost[i][j - 1] + 1);
+        }
+    }
+
+    // Walk back from the end to find the steps taken.
+    let mut steps: Vec<Step> = Vec::new();
+    let (mut i, mut j) = (typed.len(), target.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let same = matching.matches(typed[i - 1], target[j - 1]);
+            if cost[i][j] == cost[i - 1][j - 1] + !same as usize {
+                steps.push(match same {
+                    true => Step::Same(typed[i - 1]),
+                    false => Step::Wrong(typed[i - 1]),
+                });
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && cost[i][j] == cost[i - 1][j] + 1 {
+            steps.push(Step::Extra(typed[i - 1]));
+            i -= 1;
+        } else {
+            steps.push(Step::Missing(target[j - 1]));
+            j -= 1;
+        }
+    }
+
+    steps
+        .iter()
+        .rev()
+        .map(|step| match step {
+            Step::Same(letter) => letter.to_string(),
+            Step::Wrong(letter) | Step::Extra(letter) => format!("\x1b[31m{}\x1b[0m", letter),
+            Step::Missing(letter) => format!("\x1b[2;4m{}\x1b[0m", letter),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: &str = "\x1b[31m";
+    const DIM: &str = "\x1b[2;4m";
+    const END: &str = "\x1b[0m";
+
+    #[test]
+    fn right_word_is_plain() {
+        assert_eq!(diff("cat", "cat", &Matching::default()), "cat");
+    }
+
+    #[test]
+    fn marks_wrong_extra_and_missing_letters() {
+        let matching = Matching::default();
+        assert_eq!(diff("cot", "cat", &matching), format!("c{}o{}t", RED, END));
+        assert_eq!(
+            diff("cart", "cat", &matching),
+            format!("ca{}r{}t", RED, END)
+        );
+        // One missed letter doesn't make the rest of the word wrong.
+        assert_eq!(
+            diff("tiger", "tigers", &matching),
+            format!("tiger{}s{}", DIM, END)
+        );
+    }
+}
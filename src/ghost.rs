@@ -0,0 +1,124 @@
+// Ghosts: the split times of the best Race for each word count and difficulty range.
+// Stored in ghosts.txt, one line per race, as "COUNT LO HI" followed by the split of every word in
+// milliseconds since the race started.
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
+
+const GHOSTS_FILE: &str = "ghosts.txt";
+
+pub struct Ghost {
+    pub splits: Vec<Duration>,
+}
+
+impl Ghost {
+    // How many words the ghost had finished `elapsed` into the race.
+    pub fn words_at(&self, elapsed: Duration) -> usize {
+        self.splits
+            .iter()
+            .take_while(|split| **split <= elapsed)
+            .count()
+    }
+
+    // Seconds the player is behind (positive) or ahead (negative) of the ghost after `words` words.
+    pub fn delta(&self, words: usize, split: Duration) -> Option<f32> {
+        let ghost = self.splits.get(words.checked_sub(1)?)?;
+        Some(split.as_secs_f32() - ghost.as_secs_f32())
+    }
+}
+
+fn parse_line(line: &str) -> Option<((u32, u32, u32), Vec<Duration>)> {
+    let mut fields = line.split_whitespace();
+    let mut key = [0u32; 3];
+    for k in key.iter_mut() {
+        *k = fields.next()?.parse().ok()?;
+    }
+    let splits = fields
+        .map(|ms| ms.parse::<u64>().ok().map(Duration::from_millis))
+        .collect::<Option<Vec<Duration>>>()?;
+    Some(((key[0], key[1], key[2]), splits))
+}
+
+fn read_ghosts() -> Vec<((u32, u32, u32), Vec<Duration>)> {
+    match OpenOptions::new().read(true).open(Path::new(GHOSTS_FILE)) {
+        Ok(file) => BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| parse_line(&line))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn load(count: u32, low: u32, high: u32) -> Option<Ghost> {
+    read_ghosts()
+        .into_iter()
+        .find(|(key, splits)| *key == (count, low, high) && splits.len() == count as usize)
+        .map(|(_, splits)| Ghost { splits })
+}
+
+// Replaces the stored ghost if `splits` is a complete run that beats it.  Returns whether it did.
+pub fn save(count: u32, low: u32, high: u32, splits: &[Duration]) -> io::Result<bool> {
+    if splits.len() != count as usize {
+        return Ok(false);
+    }
+    let mut ghosts = read_ghosts();
+    let key = (count, low, high);
+    match ghosts.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, best)) if best.last() <= splits.last() && best.len() == splits.len() => {
+            return Ok(false)
+        }
+        Some((_, best)) => *best = splits.to_vec(),
+        None => ghosts.push((key, splits.to_vec())),
+    }
+
+    let mut contents = String::new();
+    for ((count, low, high), splits) in &ghosts {
+        contents.push_str(&format!("{} {} {}", count, low, high));
+        for split in splits {
+            contents.push_str(&format!(" {}", split.as_millis()));
+        }
+        contents.push('\n');
+    }
+    fs::write(GHOSTS_FILE, contents)?;
+    Ok(true)
+}
+
+// A progress bar for the race screen: "[#####-----] 5/10".
+pub fn bar(done: usize, count: u32) -> String {
+    const WIDTH: usize = 20;
+    let filled = (done * WIDTH)
+        .checked_div(count as usize)
+        .unwrap_or(0)
+        .min(WIDTH);
+    format!(
+        "[{}{}] {}/{}",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        done,
+        count
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ghost_lines() {
+        assert_eq!(
+            parse_line("3 1 4 1200 2500 3100"),
+            Some((
+                (3, 1, 4),
+                vec![
+                    Duration::from_millis(1200),
+                    Duration::from_millis(2500),
+                    Duration::from_millis(3100)
+                ]
+            ))
+        );
+        assert_eq!(parse_line("3 1"), None);
+        assert_eq!(parse_line("3 1 4 fast"), None);
+    }
+}
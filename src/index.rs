@@ -0,0 +1,141 @@
+// Prebuilt indexes of wordlists, so huge dictionaries load without reading and measuring every word again.
+// An index holds the words of the input files after cleaning, --top and normalization, each with its
+// difficulty.  It's named after a hash of the files and of the options that changed its words,
+// so editing a file or changing those options just makes a new index.
+//
+// The format is "TGIX", a format version, the word count, then for each word: its difficulty (1 byte),
+// its frequency (f64, NaN for none), the length of its text in bytes (u32) and the text.
+// Numbers are little-endian.
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::words::Word;
+
+pub const INDEX_DIR: &str = "index"; // Where the game keeps its indexes.
+const MAGIC: &[u8] = b"TGIX";
+const VERSION: u32 = 1;
+
+// FNV-1a.  Not cryptographic, but fast, and good enough to tell wordlists apart.
+fn hash(bytes: &[u8], mut hash: u64) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100_0000_01b3);
+    }
+    hash
+}
+
+// Key for the index of wordlists with the contents `files`, loaded with `options`.
+pub fn key(files: &[Vec<u8>], options: &str) -> u64 {
+    let mut key = hash(&VERSION.to_le_bytes(), 0xcbf2_9ce4_8422_2325);
+    for bytes in files {
+        // The length goes in too, so moving a line from the end of one file to the start of the next changes the key.
+        key = hash(bytes, hash(&(bytes.len() as u64).to_le_bytes(), key));
+    }
+    hash(options.as_bytes(), key)
+}
+
+pub fn path(dir: &Path, key: u64) -> PathBuf {
+    dir.join(format!("{:016x}.idx", key))
+}
+
+// The words saved in `dir` under `key`, or None if there's no index for it or it can't be read.
+pub fn load(dir: &Path, key: u64) -> Option<Vec<Word>> {
+    let bytes = fs::read(path(dir, key)).ok()?;
+    let mut rest = bytes.strip_prefix(MAGIC)?;
+    let mut take = |n: usize| -> Option<&[u8]> {
+        if rest.len() < n {
+            return None;
+        }
+        let (taken, left) = rest.split_at(n);
+        rest = left;
+        Some(taken)
+    };
+
+    if u32::from_le_bytes(take(4)?.try_into().ok()?) != VERSION {
+        return None;
+    }
+    let count = u64::from_le_bytes(take(8)?.try_into().ok()?) as usize;
+    // Each word takes at least 13 bytes, so a corrupt count can't ask for more room than the file has.
+    let mut words = Vec::with_capacity(count.min(bytes.len() / 13));
+    for _ in 0..count {
+        let difficulty = take(1)?[0] as u32;
+        let frequency = f64::from_le_bytes(take(8)?.try_into().ok()?);
+        let len = u32::from_le_bytes(take(4)?.try_into().ok()?) as usize;
+        let text = std::str::from_utf8(take(len)?).ok()?.to_string();
+        words.push(Word {
+            text,
+            frequency: if frequency.is_nan() {
+                None
+            } else {
+                Some(frequency)
+            },
+            difficulty: Some(difficulty),
+        });
+    }
+    Some(words)
+}
+
+// Saves words in `dir` under `key`.  Every word should have its difficulty already.
+pub fn save(dir: &Path, key: u64, words: &[Word]) -> io::Result<()> {
+    let mut bytes: Vec<u8> = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(words.len() as u64).to_le_bytes());
+    for word in words {
+        bytes.push(word.difficulty.unwrap_or(1) as u8);
+        bytes.extend_from_slice(&word.frequency.unwrap_or(f64::NAN).to_le_bytes());
+        bytes.extend_from_slice(&(word.text.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(word.text.as_bytes());
+    }
+    fs::create_dir_all(dir)?;
+    fs::write(path(dir, key), bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let key = key(&[b"round trip".to_vec()], "test");
+        let words = vec![
+            Word {
+                text: String::from("cat"),
+                frequency: Some(12.5),
+                difficulty: Some(1),
+            },
+            Word {
+                text: String::from("naïveté"),
+                frequency: None,
+                difficulty: Some(3),
+            },
+        ];
+        let dir = std::env::temp_dir();
+        save(&dir, key, &words).unwrap();
+        let loaded = load(&dir, key);
+        fs::remove_file(path(&dir, key)).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.len(), 2);
+        for (word, loaded) in words.iter().zip(&loaded) {
+            assert_eq!(word.text, loaded.text);
+            assert_eq!(word.frequency, loaded.frequency);
+            assert_eq!(word.difficulty, loaded.difficulty);
+        }
+    }
+
+    #[test]
+    fn huge_count_is_unreadable() {
+        let key = key(&[b"huge count".to_vec()], "test");
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        let dir = std::env::temp_dir();
+        fs::write(path(&dir, key), bytes).unwrap();
+        let loaded = load(&dir, key);
+        fs::remove_file(path(&dir, key)).unwrap();
+        assert!(loaded.is_none());
+    }
+}
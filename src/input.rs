@@ -0,0 +1,412 @@
+// Keyboard input for the game loops.
+// Reading whole lines with read_line() blocks until the player hits enter, so nothing on screen can
+// move in the meantime.  Here the terminal is put in raw mode and polled every few milliseconds,
+// which lets the caller redraw timers (and the ghost) while the player is still typing.
+// If raw mode is unavailable (e.g. output piped somewhere odd), we fall back to plain read_line().
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use strum_macros::AsRefStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::text::{self, Matching};
+
+const TICK: Duration = Duration::from_millis(50);
+
+// Seconds counted down before a paused game goes on.
+const RESUME_COUNTDOWN: u64 = 3;
+
+// What the player did with one call to read_word().
+pub enum Entry {
+    Word(String),
+    Quit,    // TAB, ctrl-c, ctrl-d or EOF.
+    Expired, // The redraw callback said time is up.
+}
+
+// Game time: the time since some point, leaving out time spent paused.  Every clone of a keyboard's
+// clock is stopped while the game is paused, so the game loops can time words and whole games
+// without knowing about pauses.
+#[derive(Clone, Default)]
+pub struct Clock {
+    paused: Rc<Cell<Duration>>,
+}
+
+impl Clock {
+    // Now, in game time.  Only good for comparing with other game times.
+    pub fn now(&self) -> Instant {
+        let now = Instant::now();
+        now.checked_sub(self.paused.get()).unwrap_or(now)
+    }
+
+    pub fn since(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+// What happens when the player types a wrong letter.
+#[derive(PartialEq, Eq, Clone, Copy, AsRefStr)]
+pub enum Policy {
+    Retry,    // The word can be entered wrong, and has to be typed again.
+    Continue, // The word can be entered wrong, and the game moves on.
+    Stop,     // A wrong letter isn't taken; the right one has to be typed to go on.
+    Correct,  // A wrong letter is taken, but has to be backspaced before the word can be entered.
+}
+
+impl Policy {
+    pub fn from_name(name: &str) -> Option<Policy> {
+        match name {
+            "retry" => Some(Policy::Retry),
+            "continue" => Some(Policy::Continue),
+            "stop" => Some(Policy::Stop),
+            "correct" => Some(Policy::Correct),
+            _ => None,
+        }
+    }
+}
+
+// Wrong letters typed in a word, by whether they were backspaced before it was entered.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Typos {
+    pub corrected: u32,
+    pub uncorrected: u32,
+}
+
+impl Typos {
+    pub fn add(&mut self, other: Typos) {
+        self.corrected += other.corrected;
+        self.uncorrected += other.uncorrected;
+    }
+}
+
+pub struct Keyboard {
+    raw: bool,
+    spaced: bool,              // Space ends a word, as well as enter.
+    blind: bool,               // Don't echo typing, for --blind.
+    pending: VecDeque<String>, // Words left over from a line read without raw mode.
+    policy: Policy,
+    matching: Matching,
+    target: Vec<String>,        // Letters of the word being typed.
+    wrong: u32,                 // Wrong letters typed since read_word() was called.
+    first_key: Option<Instant>, // When the first letter of the target was typed, in game time.
+    clock: Clock,
+}
+
+impl Keyboard {
+    pub fn open() -> Keyboard {
+        Keyboard {
+            raw: terminal::enable_raw_mode().is_ok(),
+            spaced: false,
+            blind: false,
+            pending: VecDeque::new(),
+            policy: Policy::Retry,
+            matching: Matching::default(),
+            target: Vec::new(),
+            wrong: 0,
+            first_key: None,
+            clock: Clock::default(),
+        }
+    }
+
+    // Makes space end a word.  Without raw mode, a line holding several words is handed out a word at a time.
+    pub fn space_submits(mut self, spaced: bool) -> Keyboard {
+        self.spaced = spaced;
+        self
+    }
+
+    // Stops echoing lines read without raw mode.  In raw mode nothing is echoed anyway, and it's
+    // up to the redraw callback whether to show the input.
+    pub fn blind(mut self, blind: bool) -> Keyboard {
+        self.blind = blind;
+        self
+    }
+
+    // Sets how wrong letters are handled, and how letters are compared to tell.
+    pub fn policy(mut self, policy: Policy, matching: Matching) -> Keyboard {
+        self.policy = policy;
+        self.matching = matching;
+        self
+    }
+
+    // Sets the word the player should be typing, so wrong letters can be caught as they're typed.
+    pub fn target(&mut self, word: &str) {
+        self.target = word.graphemes(true).map(String::from).collect();
+        self.first_key = None;
+    }
+
+    // How long after `start`, in game time, the first letter of the target was typed.  Without raw mode
+    // keystrokes can't be seen, so there's no telling.
+    pub fn reaction(&self, start: Instant) -> Option<Duration> {
+        self.first_key
+            .map(|first_key| first_key.saturating_duration_since(start))
+    }
+
+    // The game clock, which stops while the game is paused.
+    pub fn clock(&self) -> Clock {
+        self.clock.clone()
+    }
+
+    // Whether the last letter of `input` is the one the target has there.
+    fn fits(&self, input: &str) -> bool {
+        let letters = input.graphemes(true).count();
+        match (
+            input.graphemes(true).next_back(),
+            self.target.get(letters - 1),
+        ) {
+            (Some(typed), Some(wanted)) => self.matching.matches(typed, wanted),
+            _ => false,
+        }
+    }
+
+    // The wrong letters in the word just read.  Wrong letters still in `typed` are uncorrected,
+    // and any others typed along the way were corrected.  Without raw mode only the word as
+    // entered is seen, so nothing counts as corrected.
+    pub fn typos(&self, typed: &str) -> Typos {
+        let typed: Vec<&str> = typed.graphemes(true).collect();
+        let mismatched = typed
+            .iter()
+            .zip(&self.target)
+            .filter(|(typed, wanted)| !self.matching.matches(typed, wanted))
+            .count();
+        let uncorrected = (mismatched + typed.len().abs_diff(self.target.len())) as u32;
+        Typos {
+            corrected: self.wrong.saturating_sub(uncorrected),
+            uncorrected,
+        }
+    }
+
+    // Whether the player can enter `input` now.  With Policy::Correct, not until it's right.
+    fn may_enter(&self, input: &str) -> bool {
+        self.policy != Policy::Correct
+            || self.target.is_empty()
+            || self.matching.matches(input, &self.target.concat())
+    }
+
+    // Reads one word from the player.  `redraw` is handed the input typed so far, and is called
+    // on every keystroke and every tick; it returns false once the word should be cut short.
+    // Esc pauses the game, and the word picks up where it was left afterwards.
+    pub fn read_word<F>(&mut self, mut redraw: F) -> io::Result<Entry>
+    where
+        F: FnMut(&str) -> io::Result<bool>,
+    {
+        if !self.raw {
+            if let Some(word) = self.pending.pop_front() {
+                return match redraw("")? {
+                    true => Ok(Entry::Word(word)),
+                    false => Ok(Entry::Expired),
+                };
+            }
+            let entry = read_line(redraw, self.blind)?;
+            if let (true, Entry::Word(line)) = (self.spaced, &entry) {
+                self.pending = line.split_whitespace().map(String::from).collect();
+                return Ok(Entry::Word(self.pending.pop_front().unwrap_or_default()));
+            }
+            return Ok(entry);
+        }
+
+        let mut input = String::new();
+        self.wrong = 0;
+        loop {
+            if !redraw(&input)? {
+                return Ok(Entry::Expired);
+            }
+            if !event::poll(TICK)? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+                match key {
+                    key if quits(&key) => return Ok(Entry::Quit),
+                    KeyEvent {
+                        code: KeyCode::Esc, ..
+                    } => match self.pause()? {
+                        true => (),
+                        false => return Ok(Entry::Quit),
+                    },
+                    KeyEvent {
+                        code: KeyCode::Enter,
+                        ..
+                    } if self.may_enter(&input) => return Ok(Entry::Word(input)),
+                    KeyEvent {
+                        code: KeyCode::Char(' '),
+                        ..
+                    } if self.spaced && self.may_enter(&input) => return Ok(Entry::Word(input)),
+                    // Held back by Policy::Correct.
+                    KeyEvent {
+                        code: KeyCode::Enter,
+                        ..
+                    } => (),
+                    KeyEvent {
+                        code: KeyCode::Char(' '),
+                        ..
+                    } if self.spaced => (),
+                    KeyEvent {
+                        code: KeyCode::Backspace,
+                        ..
+                    } => text::pop_grapheme(&mut input),
+                    KeyEvent {
+                        code: KeyCode::Char(c),
+                        ..
+                    } => {
+                        let now = self.clock.now();
+                        self.first_key.get_or_insert(now);
+                        let mut next = input.clone();
+                        next.push(c);
+                        if !self.target.is_empty() && !self.fits(&next) {
+                            self.wrong += 1;
+                            if self.policy == Policy::Stop {
+                                continue;
+                            }
+                        }
+                        input = next;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    // Blanks the screen, so the words can't be read ahead, and stops the clock until the player
+    // presses a key, then counts down to going on.  Returns false if they quit instead.
+    fn pause(&mut self) -> io::Result<bool> {
+        let start = Instant::now();
+        draw("Paused.  The clock is stopped.\n\nPress any key to go on, or Tab to quit.")?;
+        if !self.wait()? {
+            return Ok(false);
+        }
+        for i in (1..=RESUME_COUNTDOWN).rev() {
+            draw(&format!("Going on in {}...", i))?;
+            std::thread::sleep(Duration::from_secs(1));
+        }
+        let paused = &self.clock.paused;
+        paused.set(paused.get() + start.elapsed());
+        Ok(true)
+    }
+
+    // Waits for the player to press a key, for --wait.  Returns false if they quit instead.
+    // Without raw mode, single keys can't be seen, so this waits for enter.
+    pub fn wait(&mut self) -> io::Result<bool> {
+        if !self.raw {
+            let mut input = String::new();
+            let bytes = io::stdin().lock().read_line(&mut input)?;
+            return Ok(bytes > 0 && !input.contains('\t'));
+        }
+        loop {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Release {
+                    return Ok(!quits(&key));
+                }
+            }
+        }
+    }
+}
+
+// TAB, ctrl-c and ctrl-d all quit the game.
+fn quits(key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Tab => true,
+        KeyCode::Char('c') | KeyCode::Char('d') => key.modifiers == KeyModifiers::CONTROL,
+        _ => false,
+    }
+}
+
+impl Drop for Keyboard {
+    fn drop(&mut self) {
+        if self.raw {
+            let _ = terminal::disable_raw_mode();
+        }
+    }
+}
+
+fn read_line<F>(mut redraw: F, blind: bool) -> io::Result<Entry>
+where
+    F: FnMut(&str) -> io::Result<bool>,
+{
+    if !redraw("")? {
+        return Ok(Entry::Expired);
+    }
+    let mut input = String::new();
+    let bytes = io::stdin().lock().read_line(&mut input).unwrap_or_default();
+    if !blind {
+        println!("{}", input);
+    }
+    if bytes == 0 || input.contains('\t') {
+        Ok(Entry::Quit)
+    } else {
+        Ok(Entry::Word(input.trim_end().to_string()))
+    }
+}
+
+// Width of the terminal in columns, or 80 if it can't be found.
+pub fn width() -> usize {
+    match terminal::size() {
+        Ok((columns, _)) if columns > 0 => columns as usize,
+        _ => 80,
+    }
+}
+
+// Clears the screen and prints a frame.  Raw mode doesn't translate "\n" into "\r\n", so do it here,
+// and overwrite in place rather than clearing first to keep the frame from flickering on every tick.
+pub fn draw(frame: &str) -> io::Result<()> {
+    let mut out = io::stdout();
+    write!(out, "\x1b[1;1H")?;
+    for line in frame.lines() {
+        write!(out, "{}\x1b[K\r\n", line)?;
+    }
+    write!(out, "\x1b[J")?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A keyboard that never touches the terminal, with `wrong` wrong letters typed so far.
+    fn keyboard(target: &str, wrong: u32) -> Keyboard {
+        let mut keyboard = Keyboard {
+            raw: false,
+            spaced: false,
+            blind: false,
+            pending: VecDeque::new(),
+            policy: Policy::Retry,
+            matching: Matching::default(),
+            target: Vec::new(),
+            wrong,
+            first_key: None,
+            clock: Clock::default(),
+        };
+        keyboard.target(target);
+        keyboard
+    }
+
+    fn typos(corrected: u32, uncorrected: u32) -> Typos {
+        Typos {
+            corrected,
+            uncorrected,
+        }
+    }
+
+    #[test]
+    fn clean_word_has_no_typos() {
+        assert_eq!(keyboard("cat", 0).typos("cat"), typos(0, 0));
+    }
+
+    #[test]
+    fn letters_left_in_are_uncorrected() {
+        assert_eq!(keyboard("cat", 1).typos("cot"), typos(0, 1));
+        // Missing and extra letters count too.
+        assert_eq!(keyboard("cat", 0).typos("ca"), typos(0, 1));
+        assert_eq!(keyboard("cat", 2).typos("catch"), typos(0, 2));
+    }
+
+    #[test]
+    fn letters_backspaced_are_corrected() {
+        assert_eq!(keyboard("cat", 2).typos("cat"), typos(2, 0));
+        assert_eq!(keyboard("cat", 3).typos("cot"), typos(2, 1));
+    }
+}
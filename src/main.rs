@@ -1,13 +1,32 @@
-use clap::{App, Arg, ArgGroup, ArgMatches};
-use rand::seq::SliceRandom;
-use rand::thread_rng;
-use std::collections::HashMap;
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches};
+use difficulty::{bucket_of, Progression, Ramp};
+use feedback::Messages;
+use input::{Clock, Entry, Keyboard, Policy, Typos};
+use missed::{Deck, Missed};
+use rand::{thread_rng, Rng};
+use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::fs::OpenOptions;
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, Write};
 use std::path::Path; // for parsing command line arguments
 use std::time::{Duration, Instant}; // Instant used in time attack
 use strum_macros::AsRefStr;
+use text::{length, Matching, Normalization};
+use transform::Transform;
+use vocab::{Schedule, Vocab};
+use words::{Charset, Source, Word, WordSets};
+
+mod difficulty;
+mod feedback;
+mod ghost;
+mod index;
+mod input;
+mod missed;
+mod pack;
+mod text;
+mod transform;
+mod vocab;
+mod words;
 
 /* I could make writing this into a Rust tutorial.
 -Covers dependencies
@@ -29,106 +48,274 @@ use strum_macros::AsRefStr;
 // count errors up.
 
 // implement getchar() style for "classic" argument
-const PROG_NAME: &'static str = env!("CARGO_PKG_NAME");
-const PROG_AUTHOR: &'static str = env!("CARGO_PKG_AUTHORS");
-const PROG_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+const PROG_NAME: &str = env!("CARGO_PKG_NAME");
+const PROG_AUTHOR: &str = env!("CARGO_PKG_AUTHORS");
+const PROG_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[derive(PartialEq, Eq, AsRefStr)] // You *could* download strum and derive Enum names as static str to print.
 enum Mode {
     TimeAttack,
     Endless,
     Race,
+    Timed,
+    Drill,
+    Vocab,
 }
 
 #[derive(PartialEq, Eq)]
 struct Score {
     correct: u32,
     errors: u32,
-    time: Option<Duration>, // for race
+    time: Option<Duration>,   // for race
+    active: Option<Duration>, // for race, time spent typing words, without the gaps between them
     wpm: Option<u32>,
+    splits: Vec<Duration>, // for race, time since the first keystroke at each correct word
+    stage: Option<u32>,    // for endless with lives, the difficulty reached
+    skipped: u32,          // for line, words passed over without typing them
+    letters: u32,          // for timed, letters in the words typed right
+    typos: Typos,          // wrong letters, by whether they were backspaced
+    recall: Option<Recall>, // for memory
+    words: Vec<WordStat>,  // how each word went, in order
+}
+
+// How long one word took and how many wrong letters went into it, for the breakdown at game over.
+#[derive(PartialEq, Eq)]
+struct WordStat {
+    word: String,
+    time: Duration,
+    reaction: Option<Duration>, // Time to the first keystroke.
+    typos: Typos,
+}
+
+impl WordStat {
+    fn new(word: &str, time: Duration, reaction: Option<Duration>, typos: Typos) -> WordStat {
+        WordStat {
+            word: word.to_string(),
+            time,
+            reaction,
+            typos,
+        }
+    }
+
+    fn errors(&self) -> u32 {
+        self.typos.corrected + self.typos.uncorrected
+    }
+
+    // How well a vocab word was remembered, on SM-2's scale of 0 to 5: perfectly if it was typed
+    // clean, with some hesitation if wrong letters were fixed, and not at all if it was entered wrong.
+    fn quality(&self) -> u32 {
+        match (self.typos.corrected, self.typos.uncorrected) {
+            (0, 0) => 5,
+            (_, 0) => 4,
+            _ => 2,
+        }
+    }
 }
 
-// TODO: Implement actual point-based score.
+// Words typed right on the first try, out of the words tried, for --memory.
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+struct Recall {
+    recalled: u32,
+    tried: u32,
+}
+
+impl Recall {
+    // Call on each word entered, with whether it's the first try at the current word.
+    fn record(&mut self, first_try: bool, correct: bool) {
+        if first_try {
+            self.tried += 1;
+            self.recalled += correct as u32;
+        }
+    }
+
+    fn accuracy(&self) -> f32 {
+        match self.tried {
+            0 => 0.,
+            tried => 100. * self.recalled as f32 / tried as f32,
+        }
+    }
+}
+
+// Points for playing --blind are multiplied by this.
+const BLIND_MULTIPLIER: f32 = 1.5;
+
 impl Score {
+    // Percentage of words attempted that were correct.
+    pub fn accuracy(&self) -> f32 {
+        match self.correct + self.errors + self.skipped {
+            0 => 0.,
+            attempts => 100. * self.correct as f32 / attempts as f32,
+        }
+    }
+
+    // Percentage of letters typed that were right, as typing tests count it: the letters of correct
+    // words, out of those and every wrong letter, corrected or not.
+    pub fn letter_accuracy(&self) -> f32 {
+        match self.letters + self.typos.corrected + self.typos.uncorrected {
+            0 => 0.,
+            typed => 100. * self.letters as f32 / typed as f32,
+        }
+    }
+
+    // 10 points a correct word, scaled by accuracy, then by `multiplier` for harder modifiers.
+    pub fn points(&self, multiplier: f32) -> u32 {
+        (10. * self.correct as f32 * self.accuracy() / 100. * multiplier).round() as u32
+    }
+
     pub fn default() -> Score {
         Score {
             correct: 0,
             errors: 0,
             time: None,
+            active: None,
             wpm: None,
+            splits: Vec::new(),
+            stage: None,
+            skipped: 0,
+            letters: 0,
+            typos: Typos::default(),
+            recall: None,
+            words: Vec::new(),
         }
     }
 }
 
 struct Modifiers {
-    skip_err: bool,
+    on_error: Policy,
     multiple: bool,
-    classic: bool,
+    line: bool,
     accumulate: bool,
+    blind: bool,          // Typing isn't shown, only whether each word was right.
+    memory: Option<f32>,  // Seconds each word is shown for before it's hidden, at difficulty 1.
+    transform: Transform, // Capitals, punctuation and numbers added to words.
+}
+
+// How a game starts: a welcome screen, then a countdown or a wait for the player to be ready.
+struct Start {
+    intro: f32,     // Seconds the welcome screen is up for.  0 skips it.
+    countdown: u32, // Seconds counted down before the game.  0 skips it.
+    wait: bool,     // Start on a keypress instead of a countdown.
+}
+
+// Time Attack's time bank, for --accumulate.  All in seconds.
+struct TimeBank {
+    cap: f32,
+    per_word: f32,
+    per_char: f32,
+}
+
+// Endless's lives, for --lives.  A life is lost on each error if `on_error`, and on each word
+// that takes longer than `slow` seconds.
+struct Survival {
+    lives: u32,
+    on_error: bool,
+    slow: Option<f32>,
 }
 
+// A queued word as it's shown, and as it was drawn before --capitals, --punctuation and --numbers
+// changed it.  The drawn word is None when a number took its place.
+type Queued = (String, Option<String>);
+
 struct Game {
     mode: Mode,
-    word_sets: HashMap<u32, Vec<String>>,
+    word_sets: WordSets,
     options: Modifiers,
-    count: Option<Vec<u32>>, // for race
+    count: Option<Vec<u32>>,    // for race
+    duration: Option<Duration>, // for timed
+    bank: Option<TimeBank>,     // for time attack with accumulate
+    survival: Option<Survival>, // for endless with lives
+    progression: Progression,
+    lookahead: Option<usize>,
+    matching: Matching,
+    messages: Messages,          // Said when a word is typed wrong.
+    deck: Option<RefCell<Deck>>, // for drill and vocab, the words to type
+    vocab: Option<Vocab>,        // for vocab, the definitions shown in place of the words
+    start: Start,
+    missed: RefCell<Missed>, // Words typed wrong, for --drill.
+    retire: u32,             // Clean typings for a word to leave the missed list.
 }
 
-fn read_file(filename: &str) -> Result<Vec<String>, std::io::Error> {
-    let file_path = Path::new(filename);
-    let file_object = OpenOptions::new().read(true).open(file_path)?;
-    let file_buffer = BufReader::new(file_object);
-    let words: Vec<String> = file_buffer
-        .lines()
-        .map(|l| l.unwrap().trim().to_string())
-        .collect();
-    Ok(words)
-}
-
-fn parse_to_sets(words: Vec<String>) -> HashMap<u32, Vec<String>> {
-    // Don't need to trim() the Strings because they've been trimmed in read_file().
-    // Ultimately this long branch is more readable than using a for loop, imo.
-    // (That, and trying the for loop with a vector of vectors gave me problems with references.)
+impl Game {
+    // The lines under the words: what's been typed so far, then how the last word went.
+    // With --blind, typing isn't shown.
+    fn typed_line(&self, typed: &str, last: &str) -> String {
+        match self.options.blind {
+            true => format!("\n{}", last),
+            false => format!("\n{}\n{}", typed, last),
+        }
+    }
 
-    let mut word_sets: HashMap<u32, Vec<String>> = HashMap::new();
+    // How the last word went: when it's wrong, where, and a message about it.  A right word only
+    // needs saying with --blind, since otherwise it was on screen as it was typed.
+    fn feedback<R: Rng>(&self, typed: &str, target: &str, correct: bool, rng: &mut R) -> String {
+        match (correct, self.options.blind) {
+            (true, true) => format!("\x1b[32m{}\x1b[0m", typed),
+            (true, false) => String::new(),
+            (false, _) => format!(
+                "{}  {}",
+                feedback::diff(typed, target, &self.matching),
+                self.messages.pick(rng)
+            ),
+        }
+    }
 
-    for (ch, i) in (3usize..=11usize)
-        .collect::<Vec<usize>>()
-        .chunks(3)
-        .into_iter()
-        .zip((1..=3).into_iter())
-    {
-        let three = words
-            .iter()
-            .filter(|word| word.len() == ch[0])
-            .cloned()
-            .into_iter()
-            .chain(
-                words
-                    .iter()
-                    .filter(|word| word.len() == ch[1])
-                    .cloned()
-                    .into_iter(),
-            )
-            .chain(
-                words
-                    .iter()
-                    .filter(|word| word.len() == ch[2])
-                    .cloned()
-                    .into_iter(),
-            );
-        let set: Vec<String> = three.collect();
-        word_sets.insert(i, set);
+    // The word being typed and the visible queued words.  With --memory, the word being typed is
+    // hidden once it's been up for its preview time (longer for harder words) or typing starts,
+    // and queued words stay hidden until their turn.  In Vocab mode only the definition of the
+    // word being typed is shown.
+    fn words_shown(
+        &self,
+        cur: &str,
+        words: &VecDeque<Queued>,
+        visible: usize,
+        typed: &str,
+        since: Duration,
+    ) -> String {
+        if let Some(vocab) = &self.vocab {
+            return vocab.definition(cur).to_string();
+        }
+        let preview = match self.options.memory {
+            Some(preview) => preview * bucket_of(cur) as f32,
+            None => return word_line(cur, words, visible),
+        };
+        let hide = |word: &str| (String::from("_").repeat(length(word)), length(word));
+        let cur = match typed.is_empty() && since.as_secs_f32() < preview {
+            true => (cur.to_string(), length(cur)),
+            false => hide(cur),
+        };
+        wrap(
+            std::iter::once(cur)
+                .chain(words.iter().map(|(word, _)| hide(word)))
+                .take(visible),
+        )
     }
 
-    let long: Vec<String> = words
-        .iter()
-        .cloned()
-        .filter(|word| word.len() >= 12)
-        .collect();
-    word_sets.insert(4, long);
+    // Keeps the missed list up to date: a word typed wrong goes on it, and a word typed clean counts
+    // towards leaving it.  Vocab words are about remembering the word, not typing it, so they have
+    // their own schedule instead, and numbers put in place of words aren't words to learn.
+    fn track(&self, word: Option<&str>, clean: bool) {
+        let word = match word {
+            Some(word) if self.mode != Mode::Vocab => word,
+            _ => return,
+        };
+        let mut missed = self.missed.borrow_mut();
+        match clean {
+            true => missed.clean(word, self.retire),
+            false => missed.miss(word),
+        }
+    }
 
-    word_sets
+    // A random word of the given difficulty, with the modifiers applied, along with the word as
+    // drawn, which is what the missed list records.  A drill or vocab session deals its words as
+    // they are, whatever the difficulty.
+    fn pick<R: Rng>(&self, difficulty: u32, rng: &mut R) -> Option<Queued> {
+        if let Some(deck) = &self.deck {
+            let word = deck.borrow_mut().draw(rng)?;
+            return Some((word.clone(), Some(word)));
+        }
+        let word = self.word_sets.pick(difficulty, rng)?;
+        Some(self.options.transform.apply(word, rng))
+    }
 }
 
 macro_rules! sleep {
@@ -137,18 +324,45 @@ macro_rules! sleep {
     };
 }
 
-fn count_down(secs: u32, mode: &Mode) {
-    println!("Game Mode: {}", mode.as_ref());
-
-    sleep!(2);
-    for i in (1..=secs).rev() {
+// Counts down to the start of a game, or with --wait, waits for a key so timing starts when the
+// player is ready.  Returns false if they quit instead.
+fn count_down(game: &Game) -> bool {
+    if game.start.wait {
+        println!("Game Mode: {}", game.mode.as_ref());
+        println!("Press any key when ready.  (Tab quits.)");
+        return Keyboard::open().wait().unwrap_or(false);
+    }
+    if game.start.countdown == 0 {
+        return true;
+    }
+    println!("Game Mode: {}", game.mode.as_ref());
+    for i in (1..=game.start.countdown).rev() {
         println!("{}", i);
         sleep!(1);
     }
+    true
 }
 
 fn calculate_wpm(chars_typed: usize, time_total: f32) -> u32 {
-    12 * chars_typed as u32 / time_total.round() as u32
+    12 * chars_typed as u32 / (time_total.round() as u32).max(1)
+}
+
+// "1 day", "2 days".
+fn plural(n: u64, unit: &str) -> String {
+    match n {
+        1 => format!("1 {}", unit),
+        _ => format!("{} {}s", n, unit),
+    }
+}
+
+// Seconds given to type each word at a difficulty in Time Attack.
+fn time_limit(difficulty: u32) -> f32 {
+    match difficulty {
+        1 => 10.,
+        2 => 8.,
+        3 => 6.,
+        _ => 5.,
+    }
 }
 
 // fn options(matches: &ArgMatches) -> HashMap<String, bool> {
@@ -167,17 +381,95 @@ fn calculate_wpm(chars_typed: usize, time_total: f32) -> u32 {
 //     options
 // }
 
+fn seconds(x: &str) -> Result<(), String> {
+    match x.trim().parse::<f32>() {
+        // Anything longer than an hour is a typo, and would overflow a Duration if big enough.
+        Ok(n) if (0. ..=3600.).contains(&n) => Ok(()),
+        _ => Err(String::from(
+            "Failed to parse seconds. Please give a number from 0 to 3600, like 1 or 0.5.",
+        )),
+    }
+}
+
+// Cleans up freshly read words and applies --top and normalization, leaving them ready for an index.
+fn prepare_words(words: Vec<Word>, matches: &ArgMatches, matching: &Matching) -> Vec<Word> {
+    let mut words = words::clean(
+        words,
+        &Charset::from_arg(matches.value_of("charset").unwrap_or("letters")),
+    );
+    if let Some(top) = matches.value_of("top") {
+        words::keep_top(
+            &mut words,
+            top.trim().parse::<usize>().unwrap_or(usize::MAX),
+        );
+    }
+    for word in words.iter_mut() {
+        word.text = matching.normalize(&word.text);
+    }
+    words::assign_difficulties(&mut words);
+    words
+}
+
+// The options the words loaded depend on, so an index made with other options isn't used.
+fn word_options(matches: &ArgMatches) -> String {
+    ["charset", "top", "normalize"]
+        .iter()
+        .map(|name| format!("{}={}", name, matches.value_of(name).unwrap_or("")))
+        .chain(Some(format!(
+            "lowercase={}",
+            matches.is_present("lowercase")
+        )))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Validator for chances, from 0 to 1.
+fn chance(x: &str) -> Result<(), String> {
+    match x.trim().parse::<f32>() {
+        Ok(p) if (0. ..=1.).contains(&p) => Ok(()),
+        _ => Err(String::from(
+            "Failed to parse chance. Please give a number from 0 to 1, like 0.25.",
+        )),
+    }
+}
+
 fn options(matches: &ArgMatches) -> Modifiers {
-    let skip_err = matches.is_present("skip-errors");
+    // --skip-errors and --classic are older names for two of the policies.
+    let on_error = match matches.value_of("on-error").and_then(Policy::from_name) {
+        Some(policy) => policy,
+        None if matches.is_present("skip-errors") => Policy::Continue,
+        None if matches.is_present("classic") => Policy::Stop,
+        None => Policy::Retry,
+    };
     let multiple = matches.is_present("multiple");
-    let classic = matches.is_present("classic");
+    let line = matches.is_present("line");
     let accumulate = matches.is_present("accumulate");
+    let blind = matches.is_present("blind");
+    let memory = match matches.value_of("memory") {
+        Some(value) => value.trim().parse::<f32>().ok(),
+        None if matches.is_present("memory") => Some(1.),
+        None => None,
+    };
+    // Given without a value, each modifier uses its default chance.
+    let chance = |name: &str, default: f32| match matches.value_of(name) {
+        Some(value) => value.trim().parse::<f32>().unwrap_or(default),
+        None if matches.is_present(name) => default,
+        None => 0.,
+    };
+    let transform = Transform {
+        capitals: chance("capitals", 0.25),
+        punctuation: chance("punctuation", 0.25),
+        numbers: chance("numbers", 0.1),
+    };
 
     let options: Modifiers = Modifiers {
-        skip_err,
+        on_error,
         multiple,
-        classic,
+        line,
         accumulate,
+        blind,
+        memory,
+        transform,
     };
     options
 }
@@ -186,259 +478,632 @@ fn options(matches: &ArgMatches) -> Modifiers {
 // believe that the code is more clear when organized in this fashion.
 // I can isolate bugs to a specific mode.
 fn play(game: &Game) -> Option<Score> {
-    println!("\x1b[2J\x1b[1;1HWelcome to the Typing Challenge! Type the words on screen as fast as you can, then press enter.");
-    sleep!(4);
+    if game.start.intro > 0. {
+        println!("\x1b[2J\x1b[1;1HWelcome to the Typing Challenge! Type the words on screen as fast as you can, then press enter.  Esc pauses, Tab quits.");
+        std::thread::sleep(Duration::from_secs_f32(game.start.intro));
+    }
     // TODO: change mode selection to ingame prompt.
-    return match game.mode {
+    match game.mode {
         Mode::TimeAttack => play_time(game),
+        Mode::Timed => play_timed(game),
         _ => play_race_or_endless(game),
-    };
+    }
+}
+
+// What every game loop keeps track of as words are entered: the running totals, and how the
+// word being typed is going.  Times are in game time, so pauses don't count.
+struct Tally {
+    errors: u32,
+    last: String, // How the last word went.
+    recall: Recall,
+    typos: Typos,
+    stats: Vec<WordStat>,
+    clock: Clock,
+    start: Instant, // When the word being typed came up.
+    first_try: bool,
+    word_typos: Typos,
+}
+
+impl Tally {
+    fn new(clock: Clock) -> Tally {
+        Tally {
+            errors: 0,
+            last: String::new(),
+            recall: Recall::default(),
+            typos: Typos::default(),
+            stats: Vec::new(),
+            start: clock.now(),
+            clock,
+            first_try: true,
+            word_typos: Typos::default(),
+        }
+    }
+
+    // Starts on the next word.
+    fn next(&mut self, keyboard: &mut Keyboard, cur: &str) {
+        self.start = self.clock.now();
+        self.first_try = true;
+        self.word_typos = Typos::default();
+        keyboard.target(cur);
+    }
+
+    // How long the word being typed has been up.
+    fn elapsed(&self) -> Duration {
+        self.clock.since(self.start)
+    }
+
+    // Checks a word the player entered, counting its wrong letters, and says how it went.
+    // Returns whether it was right.
+    fn enter<R: Rng>(
+        &mut self,
+        game: &Game,
+        keyboard: &Keyboard,
+        input: &str,
+        cur: &str,
+        drawn: Option<&str>,
+        rng: &mut R,
+    ) -> bool {
+        let correct = game.matching.matches(input, cur);
+        let attempt = keyboard.typos(input);
+        self.typos.add(attempt);
+        self.word_typos.add(attempt);
+        self.recall.record(self.first_try, correct);
+        self.first_try = false;
+        self.last = game.feedback(input, cur, correct, rng);
+        game.track(drawn, correct && self.word_typos == Typos::default());
+        if !correct {
+            self.errors += 1;
+        }
+        correct
+    }
+
+    // Records how the word went, once the game moves on from it.
+    fn done(&mut self, keyboard: &Keyboard, cur: &str) {
+        self.stats.push(WordStat::new(
+            cur,
+            self.elapsed(),
+            keyboard.reaction(self.start),
+            self.word_typos,
+        ));
+    }
+
+    // The score, with what the tally counted filled in.
+    fn score(self, game: &Game, correct: u32) -> Score {
+        Score {
+            correct,
+            errors: self.errors,
+            typos: self.typos,
+            recall: game.options.memory.map(|_| self.recall),
+            words: self.stats,
+            ..Score::default()
+        }
+    }
 }
 
 // as difficulty increases, you get more/less time to type the word.
 // Score is based on how many words you get correct before crashing.
 // Since losing this mode occurs on time out, skip-err is not allowed.
 // Can choose to reset timer (more score at higher diffs), or can accumulate time.
-// TODO: Implement concurrent timer which interrupts player when time is up.  Eventually implement timer.
+// With --accumulate, every word draws from one bank of time instead of its own timer.  The bank starts
+// with the first stage's time, drains while you type, and correct words pay a bonus back into it, up to the cap.
 fn play_time(game: &Game) -> Option<Score> {
-    count_down(3, &game.mode);
+    if !count_down(game) {
+        return None;
+    }
 
     let mut words_done: u32 = 0; // used to measure score
     let mut chars_typed: usize = 0; // used to measure characters per minute
     let mut words_queued: u32 = 0; // used to measure difficulties
-    let mut errors: u32 = 0;
     let mut rng = thread_rng();
-    let mut bytes;
     let mut quit = false;
 
-    let mut difficulty: u32 = 1; // initialize queue with lowest difficulty word.
-    let mut words: VecDeque<String> = VecDeque::new();
-    if game.options.multiple {
-        for _i in 0..9 {
-            let word: String = game.word_sets.get(&difficulty)?.choose(&mut rng)?.clone();
-            words_queued += 1;
-            words.push_back(word);
-        }
-    } else {
-        let word: String = game.word_sets.get(&difficulty)?.choose(&mut rng)?.clone();
+    let mut ramp = Ramp::new(game.progression, 1, 4);
+    let mut words: VecDeque<Queued> = VecDeque::new();
+    // Difficulty is based on words_queued for consistency with stages.  It just makes sense.
+    for _i in 0..queue_len(game) {
+        let word = game.pick(ramp.next(words_queued, &mut rng), &mut rng)?;
         words_queued += 1;
         words.push_back(word);
     }
 
     let mut time_difficulty: f32;
-    let mut time_passed: f32 = 0.;
     let mut time_total = 0.;
-    let mut time_accumulated: f32 = 0.;
+    let mut bank: f32 = match &game.bank {
+        Some(bonus) => time_limit(1).min(bonus.cap),
+        None => time_limit(1),
+    };
+    let mut keyboard = Keyboard::open()
+        .blind(game.options.blind)
+        .policy(game.options.on_error, game.matching);
+    let mut tally = Tally::new(keyboard.clock());
     while !quit {
-        let time_start = Instant::now();
-        let cur = words.pop_front()?.clone();
-        // Difficulty is based on words_queued for consistency with stages.  It just makes sense.
-        difficulty = match words_queued {
-            0..=20 => 1,
-            21..=40 => 2,
-            41..=60 => 3,
-            _ => 4,
-        };
-        time_difficulty = match difficulty {
-            1 => 10.,
-            2 => 8.,
-            3 => 6.,
-            _ => 5.,
+        let (cur, drawn) = words.pop_front()?;
+        tally.next(&mut keyboard, &cur);
+        time_difficulty = match game.bank {
+            Some(_) => bank,
+            None => time_limit(bucket_of(&cur)),
         };
-        time_difficulty += time_accumulated;
-        let word: String = game
-            .word_sets
-            .get(&(difficulty as u32))?
-            .choose(&mut rng)?
-            .clone();
+        let word = game.pick(ramp.next(words_queued, &mut rng), &mut rng)?;
         words_queued += 1;
         words.push_back(word);
+        let visible = visible_words(game, words_queued);
 
-        let mut input = String::new();
-        while !quit {
-            println!(
-                "\x1b[2J\x1b[1;1H{} | {} | {:.2}",
-                words_done,
-                errors,
-                time_difficulty - time_passed
-            );
-            if game.options.multiple {
-                let mut witer = words.iter();
-                println!(
-                    "\n{} {} {} {} {} {} {} {} {}",
-                    cur,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?
-                );
-            } else {
-                println!("\n{}", cur);
-            }
-            input.clear();
-            bytes = io::stdin().lock().read_line(&mut input).unwrap_or_default();
-            println!("{}", input);
-            if bytes == 0 || input.contains("\t") {
-                quit = true;
-                break;
-            }
-            if input.trim_end() == "" {
+        loop {
+            let entry = keyboard
+                .read_word(|typed| {
+                    let left = time_difficulty - tally.elapsed().as_secs_f32();
+                    let mut frame = match &game.bank {
+                        Some(bank) => format!(
+                            "{} | {} | Bank: {:.2}s / {:.0}s\n\n",
+                            words_done,
+                            tally.errors,
+                            left.max(0.),
+                            bank.cap
+                        ),
+                        None => format!(
+                            "{} | {} | {:.2}\n\n",
+                            words_done,
+                            tally.errors,
+                            left.max(0.)
+                        ),
+                    };
+                    frame.push_str(&game.words_shown(
+                        &cur,
+                        &words,
+                        visible,
+                        typed,
+                        tally.elapsed(),
+                    ));
+                    frame.push_str(&game.typed_line(typed, &tally.last));
+                    input::draw(&frame)?;
+                    Ok(left > 0.)
+                })
+                .ok()?;
+            let input = match entry {
+                Entry::Word(input) => input,
+                _ => {
+                    quit = true;
+                    break;
+                }
+            };
+            if input.is_empty() {
                 continue;
             }
 
-            if input.trim_end() == cur {
-                words_done += 1;
-                chars_typed += cur.len();
-                time_passed = Instant::now()
-                    .saturating_duration_since(time_start)
-                    .as_secs_f32();
+            if tally.enter(game, &keyboard, &input, &cur, drawn.as_deref(), &mut rng) {
+                let time_passed = tally.elapsed().as_secs_f32();
                 if time_passed >= time_difficulty {
                     quit = true;
+                    break;
                 }
-                if game.options.accumulate {
-                    time_accumulated += 1.;
-                }
+                words_done += 1;
+                tally.done(&keyboard, &cur);
+                chars_typed += length(&cur);
                 time_total += time_passed;
+                ramp.record(length(&cur), time_passed);
+                if let Some(bonus) = &game.bank {
+                    bank = (bank - time_passed
+                        + bonus.per_word
+                        + bonus.per_char * length(&cur) as f32)
+                        .min(bonus.cap);
+                }
                 break;
-            } else {
-                errors += 1;
             }
         }
     }
     if words_done > 0 {
         Some(Score {
-            correct: words_done,
-            errors,
-            time: None,
             wpm: Some(calculate_wpm(chars_typed, time_total)),
+            ..tally.score(game, words_done)
         })
     } else {
         None
     }
 }
 
-fn play_race_or_endless(game: &Game) -> Option<Score> {
-    count_down(3, &game.mode);
+// A fixed-length test: type as many words as you can before the clock runs out.
+// A word still being typed at the end doesn't count.
+fn play_timed(game: &Game) -> Option<Score> {
+    if !count_down(game) {
+        return None;
+    }
 
     let mut words_done: u32 = 0;
-    let mut errors: u32 = 0;
+    let mut chars_typed: usize = 0;
+    let mut skipped: u32 = 0;
     let mut rng = thread_rng();
-    let mut bytes;
     let mut quit = false;
-    let check: bool;
-
-    let word_count: u32;
-    let low: u32;
-    let high: u32;
-    if let Some(count_options) = &game.count {
-        word_count = count_options[0];
-        check = true;
-        match count_options.len() {
-            1 => {
-                low = 1;
-                high = 4;
+    let duration = game.duration?;
+
+    let mut ramp = Ramp::new(game.progression, 1, 4);
+    let mut words: VecDeque<Queued> = VecDeque::new();
+    for _i in 0..queue_len(game) {
+        words.push_back(game.pick(ramp.next(0, &mut rng), &mut rng)?);
+    }
+
+    let mut line = Line::new("", &words, 0);
+    let mut keyboard = Keyboard::open()
+        .blind(game.options.blind)
+        .policy(game.options.on_error, game.matching)
+        .space_submits(game.options.line);
+    let clock = keyboard.clock();
+    let mut tally = Tally::new(keyboard.clock());
+    let time_start = clock.now();
+    while !quit {
+        let (cur, drawn) = words.pop_front()?;
+        tally.next(&mut keyboard, &cur);
+        let word = game.pick(ramp.next(words_done, &mut rng), &mut rng)?;
+        words.push_back(word);
+        let visible = visible_words(game, words_done);
+        if game.options.line && line.done() {
+            line = Line::new(&cur, &words, visible);
+        }
+
+        loop {
+            let entry = keyboard
+                .read_word(|typed| {
+                    let left = duration.checked_sub(clock.since(time_start));
+                    let mut frame = format!(
+                        "{} | {} | {:.1}\n\n",
+                        words_done,
+                        tally.errors,
+                        left.unwrap_or_default().as_secs_f32()
+                    );
+                    if game.options.line {
+                        frame.push_str(&line.render());
+                    } else {
+                        frame.push_str(&game.words_shown(
+                            &cur,
+                            &words,
+                            visible,
+                            typed,
+                            tally.elapsed(),
+                        ));
+                    }
+                    frame.push_str(&game.typed_line(typed, &tally.last));
+                    input::draw(&frame)?;
+                    Ok(left.is_some())
+                })
+                .ok()?;
+            let input = match entry {
+                Entry::Word(input) => input,
+                _ => {
+                    quit = true;
+                    break;
+                }
+            };
+            if clock.since(time_start) >= duration {
+                quit = true;
+                break;
             }
-            2 => {
-                low = count_options[1];
-                high = low;
+            if input.is_empty() {
+                if game.options.line {
+                    skipped += 1;
+                    line.mark(Mark::Skipped);
+                    break;
+                }
+                continue;
             }
-            3 => {
-                low = count_options[1];
-                high = count_options[2];
+
+            if tally.enter(game, &keyboard, &input, &cur, drawn.as_deref(), &mut rng) {
+                words_done += 1;
+                tally.done(&keyboard, &cur);
+                chars_typed += length(&cur);
+                ramp.record(length(&cur), tally.elapsed().as_secs_f32());
+                line.mark(Mark::Correct);
+                break;
+            } else {
+                line.mark(Mark::Wrong);
+                match game.options.on_error == Policy::Continue || game.options.line {
+                    true => {
+                        tally.done(&keyboard, &cur);
+                        break;
+                    }
+                    false => continue,
+                }
             }
-            _ => unreachable!(), // Unreachable: vetted out by CLAP parsing
         }
+    }
+
+    // Like an unfinished race, a test quit before the clock ran out has no score.
+    if words_done > 0 && clock.since(time_start) >= duration {
+        Some(Score {
+            time: Some(duration),
+            wpm: Some(calculate_wpm(chars_typed, duration.as_secs_f32())),
+            skipped,
+            letters: chars_typed as u32,
+            ..tally.score(game, words_done)
+        })
     } else {
-        word_count = 0;
-        check = false;
-        low = 1;
-        high = 4;
+        None
+    }
+}
+
+// Word count and difficulty range for Race.  Endless has no word count and uses every difficulty.
+fn race_params(game: &Game) -> (u32, u32, u32) {
+    match &game.count {
+        Some(count_options) => match count_options.len() {
+            1 => (count_options[0], 1, 4),
+            2 => (count_options[0], count_options[1], count_options[1]),
+            3 => (count_options[0], count_options[1], count_options[2]),
+            _ => unreachable!(), // Unreachable: vetted out by CLAP parsing
+        },
+        None => (0, 1, 4),
     }
+}
 
-    let mut difficulty: u32 = low; // initialize queue with lowest difficulty word.
-    let mut words: VecDeque<String> = VecDeque::new();
+// How many words to queue up: enough for the longest line word_line() will show.
+fn queue_len(game: &Game) -> usize {
+    match (game.options.multiple, game.lookahead) {
+        (_, Some(lookahead)) => lookahead,
+        (true, None) => 9,
+        (false, None) => 1,
+    }
+}
+
+// How many words are on screen `words` words into a game, counting the one being typed.
+// --lookahead alone shows that many words.  With --multiple, more of them are shown every 20 words,
+// whatever the progression, so a game with random difficulties doesn't show them all from the start.
+fn visible_words(game: &Game, words: u32) -> usize {
     if game.options.multiple {
-        for _i in 0..9 {
-            // queue up nine words, so the tenth is added in the loop.
-            let word: String = game.word_sets.get(&difficulty)?.choose(&mut rng)?.clone();
-            difficulty = match game.mode {
-                Mode::Race => ((word.len() as u32) % (high - low + 1)) + low,
-                Mode::Endless => (word.len() as u32 % 4) + 1,
-                _ => unreachable!(), // Unreachable because only called for above two modes
-            };
-            words.push_back(word);
-        }
+        let stage = (words / 20 + 1).min(4) as usize;
+        (queue_len(game) * stage).div_ceil(4).max(1)
     } else {
-        let word: String = game.word_sets.get(&low)?.choose(&mut rng)?.clone();
-        words.push_back(word);
+        queue_len(game)
     }
+}
 
-    let now: Option<Instant>;
-    if game.mode == Mode::Race {
-        now = Some(Instant::now());
-    } else {
-        now = None;
+// The word being typed, followed by as many queued words as are visible, wrapped to the terminal width.
+fn word_line(cur: &str, words: &VecDeque<Queued>, visible: usize) -> String {
+    wrap(
+        std::iter::once(cur)
+            .chain(words.iter().map(|(word, _)| word.as_str()))
+            .take(visible)
+            .map(|word| (word.to_string(), length(word))),
+    )
+}
+
+// Joins words with spaces, breaking lines at the terminal width.  Each word comes with its length
+// on screen, since it may be wrapped in colour codes.
+fn wrap<I: Iterator<Item = (String, usize)>>(words: I) -> String {
+    let width = input::width();
+    let mut line = String::new();
+    let mut column = 0;
+    for (word, len) in words {
+        if column > 0 && column + 1 + len > width {
+            line.push('\n');
+            column = 0;
+        } else if column > 0 {
+            line.push(' ');
+            column += 1;
+        }
+        line.push_str(&word);
+        column += len;
     }
+    line
+}
+
+// How a word in a --line row went.
+#[derive(Clone, Copy)]
+enum Mark {
+    Correct,
+    Wrong,
+    Skipped,
+}
+
+// The row of words being typed with --line.  It stays on screen until every word in it is marked.
+struct Line {
+    words: Vec<String>,
+    marks: Vec<Mark>,
+}
+
+impl Line {
+    fn new(cur: &str, words: &VecDeque<Queued>, visible: usize) -> Line {
+        Line {
+            words: std::iter::once(cur)
+                .chain(words.iter().map(|(word, _)| word.as_str()))
+                .take(visible)
+                .map(String::from)
+                .collect(),
+            marks: Vec::new(),
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.marks.len() >= self.words.len()
+    }
+
+    fn mark(&mut self, mark: Mark) {
+        if !self.done() {
+            self.marks.push(mark);
+        }
+    }
+
+    // The row, green for correct words, red for wrong ones and dim for skipped ones.
+    // The word being typed is underlined.
+    fn render(&self) -> String {
+        wrap(self.words.iter().enumerate().map(|(i, word)| {
+            let style = match self.marks.get(i) {
+                Some(Mark::Correct) => "\x1b[32m",
+                Some(Mark::Wrong) => "\x1b[31m",
+                Some(Mark::Skipped) => "\x1b[2m",
+                None if i == self.marks.len() => "\x1b[4m",
+                None => "",
+            };
+            (format!("{}{}\x1b[0m", style, word), length(word))
+        }))
+    }
+}
+
+fn play_race_or_endless(game: &Game) -> Option<Score> {
+    if !count_down(game) {
+        return None;
+    }
+
+    let mut words_done: u32 = 0;
+    let mut rng = thread_rng();
+    let mut quit = false;
+    let check = game.count.is_some();
+    let (word_count, low, high) = race_params(game);
+    let mut lives = game.survival.as_ref().map(|survival| survival.lives);
+    let mut skipped: u32 = 0;
+
+    let mut ramp = Ramp::new(game.progression, low, high);
+    let mut words: VecDeque<Queued> = VecDeque::new();
+    // queue up every visible word, so the next one is added in the loop.
+    for _i in 0..queue_len(game) {
+        words.push_back(game.pick(ramp.next(0, &mut rng), &mut rng)?);
+    }
+
+    let ghost = match game.mode {
+        Mode::Race => ghost::load(word_count, low, high),
+        _ => None,
+    };
+    let mut splits: Vec<Duration> = Vec::new();
+    let mut line = Line::new("", &words, 0);
+    let mut keyboard = Keyboard::open()
+        .blind(game.options.blind)
+        .policy(game.options.on_error, game.matching)
+        .space_submits(game.options.line);
+    let clock = keyboard.clock();
+    let mut tally = Tally::new(keyboard.clock());
+    // The race clock starts on the first keystroke and stops on the last letter of the last word,
+    // so reading the first word and pressing enter at the end don't count.  Without raw mode,
+    // keystrokes can't be seen, so it runs from the first word being shown to the last one entered.
+    let race = game.mode == Mode::Race;
+    let shown = clock.now();
+    let mut now: Option<Instant> = None; // When the first key was typed.
+    let mut typed_at = shown; // When the input last changed.
+    let mut seen = String::new();
+    let mut active = Duration::default();
 
     while !quit {
-        let cur = words.pop_front()?.clone();
-        difficulty = match game.mode {
-            Mode::Race => ((cur.len() as u32) % (high - low + 1)) + low,
-            Mode::Endless => cur.len() as u32 % 4 + 1,
-            _ => unreachable!(), // Unreachable because only called for above two modes
-        };
-        let word: String = game
-            .word_sets
-            .get(&(difficulty as u32))?
-            .choose(&mut rng)?
-            .clone();
+        let (cur, drawn) = words.pop_front()?;
+        tally.next(&mut keyboard, &cur);
+        let word = game.pick(ramp.next(words_done, &mut rng), &mut rng)?;
         words.push_back(word);
+        let visible = visible_words(game, words_done);
+        if game.options.line && line.done() {
+            line = Line::new(&cur, &words, visible);
+        }
 
-        let mut input = String::new();
-        while !quit {
-            println!("\x1b[2J\x1b[1;1H{} | {}", words_done, errors);
-            if game.options.multiple {
-                let mut witer = words.iter();
-                println!(
-                    "\n{} {} {} {} {} {} {} {} {}",
-                    cur,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?,
-                    witer.next()?
-                );
-            } else {
-                println!("\n{}", cur);
-            }
-            input.clear();
-            bytes = io::stdin().lock().read_line(&mut input).unwrap_or_default();
-            println!("{}", input);
-            if bytes == 0 || input.contains("\t") {
-                quit = true;
-                break;
+        loop {
+            let entry = keyboard
+                .read_word(|typed| {
+                    if typed != seen {
+                        seen = typed.to_string();
+                        typed_at = clock.now();
+                        now.get_or_insert(typed_at);
+                    }
+                    let mut frame = match lives {
+                        Some(lives) => format!(
+                            "{} | {} | Lives: {} | Stage: {}\n\n",
+                            words_done,
+                            tally.errors,
+                            lives,
+                            ramp.level(words_done)
+                        ),
+                        None => format!("{} | {}\n\n", words_done, tally.errors),
+                    };
+                    if let (true, Some(ghost)) = (race, &ghost) {
+                        let elapsed = now.map(|start| clock.since(start)).unwrap_or_default();
+                        let ghost_done = ghost.words_at(elapsed);
+                        frame.push_str(&format!(
+                            "You   {}\nGhost {}",
+                            ghost::bar(words_done as usize, word_count),
+                            ghost::bar(ghost_done, word_count)
+                        ));
+                        match splits
+                            .last()
+                            .and_then(|split| ghost.delta(splits.len(), *split))
+                        {
+                            Some(delta) if delta > 0. => {
+                                frame.push_str(&format!("  (behind by {:.2}s)", delta))
+                            }
+                            Some(delta) => frame.push_str(&format!("  (ahead by {:.2}s)", -delta)),
+                            None => (),
+                        }
+                        frame.push_str("\n\n");
+                    }
+                    if game.options.line {
+                        frame.push_str(&line.render());
+                    } else {
+                        frame.push_str(&game.words_shown(
+                            &cur,
+                            &words,
+                            visible,
+                            typed,
+                            tally.elapsed(),
+                        ));
+                    }
+                    frame.push_str(&game.typed_line(typed, &tally.last));
+                    input::draw(&frame)?;
+                    Ok(true)
+                })
+                .ok()?;
+            // Nothing typed was seen, so there's no raw mode: go by when the word was entered.
+            if seen.is_empty() {
+                typed_at = clock.now();
+                now.get_or_insert(shown);
             }
-            if input.trim_end() == "" {
+            seen.clear();
+            let input = match entry {
+                Entry::Word(input) => input,
+                _ => {
+                    quit = true;
+                    break;
+                }
+            };
+            if input.is_empty() {
+                if game.options.line {
+                    skipped += 1;
+                    line.mark(Mark::Skipped);
+                    break;
+                }
                 continue;
             }
 
-            if input.trim_end() == cur {
+            if tally.enter(game, &keyboard, &input, &cur, drawn.as_deref(), &mut rng) {
                 words_done += 1;
+                tally.done(&keyboard, &cur);
+                ramp.record(length(&cur), tally.elapsed().as_secs_f32());
+                line.mark(Mark::Correct);
+                if let (true, Some(start)) = (race, now) {
+                    splits.push(typed_at.saturating_duration_since(start));
+                    // From the word's first keystroke to its last letter.
+                    let first_key =
+                        tally.start + keyboard.reaction(tally.start).unwrap_or_default();
+                    active += typed_at.saturating_duration_since(first_key);
+                }
                 if check && words_done == word_count {
                     quit = true;
                 }
+                if let (Some(survival), Some(left)) = (&game.survival, lives.as_mut()) {
+                    match survival.slow {
+                        Some(slow) if tally.elapsed().as_secs_f32() > slow => {
+                            *left -= 1;
+                            quit = *left == 0;
+                        }
+                        _ => (),
+                    }
+                }
                 break;
             } else {
-                errors += 1;
-                match game.options.skip_err {
-                    true => break,
+                line.mark(Mark::Wrong);
+                if let (Some(survival), Some(left)) = (&game.survival, lives.as_mut()) {
+                    if survival.on_error {
+                        *left -= 1;
+                        if *left == 0 {
+                            quit = true;
+                            break;
+                        }
+                    }
+                }
+                match game.options.on_error == Policy::Continue || game.options.line {
+                    true => {
+                        tally.done(&keyboard, &cur);
+                        break;
+                    }
                     false => continue,
                 }
             }
@@ -451,10 +1116,11 @@ fn play_race_or_endless(game: &Game) -> Option<Score> {
                 None
             } else {
                 Some(Score {
-                    correct: words_done,
-                    errors,
-                    time: Some(now?.elapsed()),
-                    wpm: None,
+                    time: splits.last().copied(),
+                    active: Some(active),
+                    splits,
+                    skipped,
+                    ..tally.score(game, words_done)
                 })
             }
         }
@@ -463,26 +1129,119 @@ fn play_race_or_endless(game: &Game) -> Option<Score> {
                 None
             } else {
                 Some(Score {
-                    correct: words_done,
-                    errors,
-                    time: None,
-                    wpm: None,
+                    stage: lives.map(|_| ramp.level(words_done)),
+                    skipped,
+                    ..tally.score(game, words_done)
                 })
             }
         }
     }
 }
 
+// How many of the slowest words and of the words with the most errors to show.
+const BREAKDOWN: usize = 5;
+
+// Prints the slowest words and the words with the most errors, with average times per word.
+fn print_breakdown(words: &[WordStat]) {
+    if words.is_empty() {
+        return;
+    }
+    let seconds = |time: Option<Duration>| match time {
+        Some(time) => format!("{:.2}s", time.as_secs_f32()),
+        None => String::from("-"),
+    };
+    let table = |title: &str, words: &[&WordStat]| {
+        println!("\n{}", title);
+        println!(
+            "  {:<20} {:>8} {:>9} {:>7}",
+            "WORD", "TIME", "REACTION", "ERRORS"
+        );
+        for stat in words {
+            println!(
+                "  {:<20} {:>8} {:>9} {:>7}",
+                stat.word,
+                seconds(Some(stat.time)),
+                seconds(stat.reaction),
+                stat.errors()
+            );
+        }
+    };
+
+    let mut slowest: Vec<&WordStat> = words.iter().collect();
+    slowest.sort_by_key(|stat| std::cmp::Reverse(stat.time));
+    slowest.truncate(BREAKDOWN);
+    table("Slowest words:", &slowest);
+
+    let mut most_errors: Vec<&WordStat> = words.iter().filter(|stat| stat.errors() > 0).collect();
+    most_errors.sort_by_key(|stat| std::cmp::Reverse(stat.errors()));
+    most_errors.truncate(BREAKDOWN);
+    if !most_errors.is_empty() {
+        table("Most errors:", &most_errors);
+    }
+
+    let reactions: Vec<Duration> = words.iter().filter_map(|stat| stat.reaction).collect();
+    println!(
+        "\nTime per word: {} | Reaction time: {}",
+        seconds(Some(
+            words.iter().map(|stat| stat.time).sum::<Duration>() / words.len() as u32
+        )),
+        seconds(match reactions.len() {
+            0 => None,
+            n => Some(reactions.iter().sum::<Duration>() / n as u32),
+        })
+    );
+}
+
 // Print score and write it to scores.txt.
 fn give_score(scores: &Score, mode: &Mode, options: &Modifiers) -> io::Result<()> {
     write_score(scores, mode, options)?;
     println!("\x1b[2J\x1b[1;1HGood game! Your score:");
     print!("Correct: {} | Errors: {}", scores.correct, scores.errors);
-    if let Some(time) = scores.time {
-        println!(" | Race time: {}", time.as_secs_f32().to_string());
+    if options.line {
+        print!(" | Skipped: {}", scores.skipped);
+    }
+    if *mode == Mode::Timed {
+        println!(
+            " | WPM: {} | Accuracy: {:.1}%",
+            scores.wpm.unwrap_or(0),
+            scores.letter_accuracy()
+        );
+    } else if *mode == Mode::Drill || *mode == Mode::Vocab {
+        println!(" | Accuracy: {:.1}%", scores.accuracy());
+    } else if let Some(time) = scores.time {
+        print!(" | Race time: {}", time.as_secs_f32());
+        match scores.active {
+            Some(active) => println!(" (typing: {:.2})", active.as_secs_f32()),
+            None => println!(),
+        }
     } else if let Some(wpm) = scores.wpm {
         println!(" | Approx. WPM: {}", wpm);
+    } else if let Some(stage) = scores.stage {
+        println!(" | Stage reached: {}", stage);
+    } else {
+        println!();
     }
+    println!(
+        "Wrong letters: {} corrected, {} uncorrected",
+        scores.typos.corrected, scores.typos.uncorrected
+    );
+    if let Some(recall) = scores.recall {
+        println!(
+            "Recall: {:.1}% ({} of {} words right on the first try)",
+            recall.accuracy(),
+            recall.recalled,
+            recall.tried
+        );
+    }
+    match options.blind {
+        true => println!(
+            "Points: {} (x{} for --blind)",
+            scores.points(BLIND_MULTIPLIER),
+            BLIND_MULTIPLIER
+        ),
+        false => println!("Points: {}", scores.points(1.)),
+    }
+    print_breakdown(&scores.words);
 
     Ok(())
 }
@@ -495,22 +1254,46 @@ fn write_score(scores: &Score, mode: &Mode, options: &Modifiers) -> io::Result<(
         .open(scores_path)
         .expect("Expected valid path creation from \"scores.txt\".");
 
-    let mode_string = if options.accumulate {
-        mode.as_ref().to_owned() + " -a"
-    } else {
-        mode.as_ref().to_owned()
-    };
+    let mut mode_string = mode.as_ref().to_owned();
+    if let (Mode::Timed, Some(duration)) = (mode, scores.time) {
+        mode_string += &format!(" {}s", duration.as_secs());
+    }
+    if options.accumulate {
+        mode_string += " -a";
+    }
+    if scores.stage.is_some() {
+        mode_string += " -l";
+    }
+    if options.line {
+        mode_string += " -L";
+    }
+    if options.blind {
+        mode_string += " --blind";
+    }
+    if options.on_error != Policy::Retry {
+        mode_string += &format!(" --on-error {}", options.on_error.as_ref().to_lowercase());
+    }
+    if let Some(preview) = options.memory {
+        mode_string += &format!(" --memory={}", preview);
+    }
+    mode_string += &options.transform.flags();
 
     write!(
         &mut scores_file,
         "\nMode: {:<12}  :  Correct: {:<5} |  Errors: {:<5}",
         mode_string, scores.correct, scores.errors
     )?;
+    if options.line {
+        write!(&mut scores_file, "  |  Skipped: {:<5}", scores.skipped)?;
+    }
 
     match *mode {
         Mode::Race => {
             if let Some(duration) = scores.time {
                 write!(&mut scores_file, "  |  Time: {:.2}", duration.as_secs_f32())?;
+                if let Some(active) = scores.active {
+                    write!(&mut scores_file, "  |  Typing: {:.2}", active.as_secs_f32())?;
+                }
             } else {
                 write!(&mut scores_file, "  |  Time: N/A")?;
             }
@@ -522,7 +1305,63 @@ fn write_score(scores: &Score, mode: &Mode, options: &Modifiers) -> io::Result<(
                 write!(&mut scores_file, "  |  Approx. WPM: N/A")?;
             }
         }
-        _ => (),
+        Mode::Timed => {
+            write!(
+                &mut scores_file,
+                "  |  WPM: {}  |  Accuracy: {:.1}%",
+                scores.wpm.unwrap_or(0),
+                scores.letter_accuracy()
+            )?;
+        }
+        Mode::Endless => {
+            if let Some(stage) = scores.stage {
+                write!(&mut scores_file, "  |  Stage: {}", stage)?;
+            }
+        }
+        Mode::Drill | Mode::Vocab => {
+            write!(&mut scores_file, "  |  Accuracy: {:.1}%", scores.accuracy())?;
+        }
+    }
+    let multiplier = match options.blind {
+        true => BLIND_MULTIPLIER,
+        false => 1.,
+    };
+    write!(
+        &mut scores_file,
+        "  |  Points: {}",
+        scores.points(multiplier)
+    )?;
+    write!(
+        &mut scores_file,
+        "  |  Corrected: {}  |  Uncorrected: {}",
+        scores.typos.corrected, scores.typos.uncorrected
+    )?;
+    if let Some(recall) = scores.recall {
+        write!(&mut scores_file, "  |  Recall: {:.1}%", recall.accuracy())?;
+    }
+    // The breakdown goes on its own line: each word with its time, reaction time and wrong letters.
+    if !scores.words.is_empty() {
+        let words: Vec<String> = scores
+            .words
+            .iter()
+            .map(|stat| {
+                format!(
+                    "{} {:.2}/{}/{}",
+                    stat.word,
+                    stat.time.as_secs_f32(),
+                    stat.reaction.map_or(String::from("-"), |reaction| format!(
+                        "{:.2}",
+                        reaction.as_secs_f32()
+                    )),
+                    stat.errors()
+                )
+            })
+            .collect();
+        write!(
+            &mut scores_file,
+            "\n    Words (time/reaction/errors): {}",
+            words.join(", ")
+        )?;
     }
 
     Ok(())
@@ -535,10 +1374,70 @@ fn main() -> io::Result<()> {
         .about("A command-line typing game.  Input a TAB character to end a game.")
         .arg(
             Arg::with_name("input-file")
-                .about("File containing word data for game.")
+                .about("Files containing word data for game.  Directories and globs like \"lists/*.txt\" read every file in them, and \"-\" reads from stdin.  Add \"@WEIGHT\" to a file to make its words come up that many times as often, like \"jargon.txt@3\".")
                 .value_name("FILE")
+                .multiple(true)
                 .index(1)
-                .required(true),
+                .required_unless_one(&["pack", "from-text", "drill", "vocab"]),
+        )
+        .arg(
+            Arg::with_name("from-text")
+                .long("from-text")
+                .value_name("FILE")
+                .multiple(true)
+                .number_of_values(1)
+                .conflicts_with("pack")
+                .about("Plays with the words in any text, like your documentation or code, instead of a wordlist.  Punctuation and numbers are left out, and words that come up often count as common for --top and --weighted.  Takes files, directories, globs and \"-\" like input files.  Can be given more than once."),
+        )
+        .arg(
+            Arg::with_name("lowercase")
+                .long("lowercase")
+                .requires("from-text")
+                .about("Lowercases the words taken from --from-text, so \"The\" and \"the\" are one word."),
+        )
+        .arg(
+            Arg::with_name("pack")
+                .short('P')
+                .long("pack")
+                .value_name("NAME")
+                .conflicts_with("input-file")
+                .about("Plays with an installed word pack instead of an input file.  See \"packs list\"."),
+        )
+        .arg(
+            Arg::with_name("top")
+                .long("top")
+                .value_name("N")
+                .validator(|x| match x.trim().parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(String::from("Failed to parse --top. Please give a positive integer.")),
+                })
+                .about("Only use the N most common words.  Uses the frequency column of the input file or the ranks in a pack, or else takes the first N words."),
+        )
+        .arg(
+            Arg::with_name("weighted")
+                .short('w')
+                .long("weighted")
+                .about("When enabled, common words come up more often, in proportion to their frequency.  Needs a frequency column in the input file, or a pack with ranks."),
+        )
+        .arg(
+            Arg::with_name("charset")
+                .long("charset")
+                .value_name("SET")
+                .default_value("letters")
+                .about("Which characters words may have: \"letters\" (any alphabet, with accents, apostrophes and hyphens), \"ascii\" (a to z), \"any\", or a list of the characters allowed.  Other words are left out."),
+        )
+        .arg(
+            Arg::with_name("index")
+                .long("index")
+                .conflicts_with("pack")
+                .about("Saves the words of the input file in an index in the \"index\" directory, and loads them from there next time, for fast startup with huge wordlists.  The index is rebuilt when the file changes."),
+        )
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            App::new("packs")
+                .about("Manages word packs in the packs directory.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(App::new("list").about("Lists installed word packs.")),
         )
         .arg(
             Arg::with_name("time-attack")
@@ -574,17 +1473,117 @@ fn main() -> io::Result<()> {
                 })
                 .about("Enters the game in Race mode.  Input number of words to type."),
         )
+        .arg(
+            Arg::with_name("timed")
+                .short('T')
+                .long("timed")
+                .about("Enters the game in Timed mode.  Type as many words as you can before time runs out."),
+        )
         .group(
             ArgGroup::with_name("modes")
-                .args(&["time-attack", "endless", "race"])
+                .args(&["time-attack", "endless", "race", "timed", "drill", "vocab"])
                 .required(true),
         )
+        .arg(
+            Arg::with_name("drill")
+                .long("drill")
+                .about("Enters the game in Drill mode: type each of the words you've missed before.  A word leaves the list once you've typed it right --retire times in a row, in any mode."),
+        )
+        .arg(
+            Arg::with_name("vocab")
+                .long("vocab")
+                .value_name("FILE")
+                .conflicts_with("line")
+                .about("Enters the game in Vocab mode: type the word for each definition in FILE, one \"word = definition\" per line.  Words come back for review on a spaced repetition schedule, kept per --player."),
+        )
+        .arg(
+            Arg::with_name("new")
+                .long("new")
+                .value_name("N")
+                .requires("vocab")
+                .validator(|x| match x.trim().parse::<usize>() {
+                    Ok(_) => Ok(()),
+                    _ => Err(String::from("Failed to parse --new. Please give a whole number.")),
+                })
+                .about("How many words not reviewed yet to add to a Vocab session.  Defaults to 10."),
+        )
+        .arg(
+            Arg::with_name("player")
+                .long("player")
+                .value_name("NAME")
+                .default_value("default")
+                .validator(|x| match x.is_empty() || x.contains(char::is_whitespace) {
+                    true => Err(String::from("Player names can't be empty or have spaces.")),
+                    false => Ok(()),
+                })
+                .about("Whose missed words to record and drill, and whose vocab reviews to keep."),
+        )
+        .arg(
+            Arg::with_name("retire")
+                .long("retire")
+                .value_name("N")
+                .default_value("3")
+                .validator(|x| match x.trim().parse::<u32>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(String::from("Failed to parse --retire. Please give a positive integer.")),
+                })
+                .about("How many times in a row a missed word has to be typed right to leave the missed list."),
+        )
+        .arg(
+            Arg::with_name("intro")
+                .long("intro")
+                .value_name("SECS")
+                .validator(seconds)
+                .about("How long the welcome screen is shown before the game.  0 skips it.  Defaults to 4."),
+        )
+        .arg(
+            Arg::with_name("countdown")
+                .long("countdown")
+                .value_name("SECS")
+                .validator(|x| match x.trim().parse::<u32>() {
+                    Ok(_) => Ok(()),
+                    _ => Err(String::from("Failed to parse --countdown. Please give a whole number of seconds.")),
+                })
+                .about("How many seconds to count down before the game.  0 skips the countdown.  Defaults to 3."),
+        )
+        .arg(
+            Arg::with_name("no-countdown")
+                .long("no-countdown")
+                .conflicts_with_all(&["intro", "countdown"])
+                .about("Starts the game straight away, without the welcome screen or countdown.  Same as --intro 0 --countdown 0."),
+        )
+        .arg(
+            Arg::with_name("wait")
+                .long("wait")
+                .conflicts_with("countdown")
+                .about("Instead of counting down, waits for you to press a key, and starts timing then."),
+        )
+        .arg(
+            Arg::with_name("duration")
+                .short('d')
+                .long("duration")
+                .value_name("SECS")
+                .requires("timed")
+                .validator(|x| match x.trim().parse::<u64>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(String::from("Failed to parse duration. Please give a positive number of seconds, like 15, 30, 60 or 120.")),
+                })
+                .about("Length of a Timed game in seconds.  Defaults to 60."),
+        )
         .arg(   // lower/higher score.
             Arg::with_name("skip-errors")
                 .short('s')
                 .long("skip-errors")
                 .conflicts_with("time-attack")
-                .about("When enabled, skips past errors and counts them to display at game over."),
+                .about("When enabled, skips past errors and counts them to display at game over.  Same as --on-error continue."),
+        )
+        .arg(
+            Arg::with_name("on-error")
+                .long("on-error")
+                .value_name("POLICY")
+                .possible_values(&["retry", "continue", "stop", "correct"])
+                .conflicts_with_all(&["skip-errors", "classic"])
+                .about("What happens on a mistake.  \"retry\" (the default): a wrong word has to be typed again.  \"continue\": a wrong word is counted and the game moves on.  \"stop\": a wrong letter isn't taken until you type the right one.  \"correct\": a wrong letter has to be backspaced before the word can be entered.  Wrong letters are counted as corrected or uncorrected either way."),
         )
         .arg(   // allows you to see multiple words at once, so you can look ahead.
             Arg::with_name("multiple")
@@ -592,24 +1591,249 @@ fn main() -> io::Result<()> {
                 .long("multiple")
                 .about("When enabled, higher difficulties display multiple words. Recommended."),
         )
-        .arg(   // TODO: Implement char by char checking.
+        .arg(
+            Arg::with_name("line")
+                .short('L')
+                .long("line")
+                .conflicts_with("time-attack")
+                .about("When enabled, type the whole row of words on screen, pressing space after each one.  Wrong words are not retyped, and pressing space on an empty word skips it.  Use with --multiple or --lookahead."),
+        )
+        .arg(
+            Arg::with_name("normalize")
+                .long("normalize")
+                .value_name("nfc | nfd")
+                .possible_values(&["nfc", "nfd"])
+                .about("Unicode normalization applied to words and to what you type, so accents typed as one character or as a letter plus a combining mark both match."),
+        )
+        .arg(
+            Arg::with_name("ignore-accents")
+                .long("ignore-accents")
+                .about("When enabled, accents don't matter: typing \"cafe\" matches \"café\"."),
+        )
+        .arg(
+            Arg::with_name("lookahead")
+                .long("lookahead")
+                .value_name("WORDS")
+                .validator(|x| match x.trim().parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(String::from("Failed to parse lookahead. Please give a positive integer.")),
+                })
+                .about("How many words to display, counting the one being typed.  With --multiple, this is the most shown at the highest difficulty.  Defaults to 9 with --multiple."),
+        )
+        .arg(
+            Arg::with_name("capitals")
+                .long("capitals")
+                .value_name("CHANCE")
+                .min_values(0)
+                .require_equals(true)
+                .validator(chance)
+                .about("Capitalizes some words.  CHANCE is how often, from 0 to 1, like --capitals=0.5 (default 0.25)."),
+        )
+        .arg(
+            Arg::with_name("punctuation")
+                .long("punctuation")
+                .value_name("CHANCE")
+                .min_values(0)
+                .require_equals(true)
+                .validator(chance)
+                .about("Adds commas, periods and other punctuation to some words, or puts them in quotes or brackets.  CHANCE is how often, from 0 to 1 (default 0.25)."),
+        )
+        .arg(
+            Arg::with_name("numbers")
+                .long("numbers")
+                .value_name("CHANCE")
+                .min_values(0)
+                .require_equals(true)
+                .validator(chance)
+                .about("Swaps some words for numbers.  CHANCE is how often, from 0 to 1 (default 0.1)."),
+        )
+        .arg(
             Arg::with_name("classic")
                 .short('c')
                 .long("classic")
-                .about("When enabled, typing is parsed char by char (and so are errors)."),
+                .about("When enabled, typing is checked char by char: a wrong letter isn't taken until you type the right one.  Same as --on-error stop."),
+        )
+        .arg(
+            Arg::with_name("blind")
+                .long("blind")
+                .about("Hides what you type.  You only see whether each word was right after you enter it.  Scores more points, and is recorded separately."),
+        )
+        .arg(
+            Arg::with_name("memory")
+                .long("memory")
+                .value_name("SECS")
+                .min_values(0)
+                .require_equals(true)
+                .validator(seconds)
+                .conflicts_with("line")
+                .about("Shows each word for a moment, then hides it, so you type it from memory.  SECS is how long the easiest words are shown, like --memory=0.5 (default 1); harder words are shown longer.  Typing hides the word straight away.  Your recall accuracy is recorded with your score."),
+        )
+        .arg(
+            Arg::with_name("messages")
+                .long("messages")
+                .value_name("FILE")
+                .about("Picks what's said when you type a word wrong from FILE, one message per line, instead of the built-in messages."),
         )
         .arg(
             Arg::with_name("accumulate")
                 .short('a')
                 .long("accumulate")
                 .requires("time-attack")
-                .about("When enabled in Time Attack mode, allows each correct word to add to the timer until 30 seconds.")
+                .about("When enabled in Time Attack mode, all words share one bank of time, and each correct word adds to it up to a cap.")
+        )
+        .arg(
+            Arg::with_name("cap")
+                .long("cap")
+                .value_name("SECS")
+                .requires("accumulate")
+                .validator(|x| match seconds(x) {
+                    Ok(()) if x.trim().parse::<f32>() != Ok(0.) => Ok(()),
+                    Ok(()) => Err(String::from("The bank can't be capped at 0 seconds.")),
+                    error => error,
+                })
+                .about("The most time the bank can hold with --accumulate.  Defaults to 30."),
+        )
+        .arg(
+            Arg::with_name("bonus-word")
+                .long("bonus-word")
+                .value_name("SECS")
+                .requires("accumulate")
+                .validator(seconds)
+                .about("Time added to the bank for each correct word with --accumulate.  Defaults to 1."),
+        )
+        .arg(
+            Arg::with_name("bonus-char")
+                .long("bonus-char")
+                .value_name("SECS")
+                .requires("accumulate")
+                .validator(seconds)
+                .about("Time added to the bank for each character of a correct word with --accumulate.  Defaults to 0."),
+        )
+        .arg(
+            Arg::with_name("progression")
+                .short('p')
+                .long("progression")
+                .value_name("fixed | count | speed | random")
+                .possible_values(&["fixed", "count", "speed", "random"])
+                .about("How word difficulty changes over a game: stay at the lowest, go up every 20 words, follow your typing speed, or pick randomly.  Race's difficulty range still applies.  Defaults to count for Time Attack and --lives, fixed for Timed, random otherwise."),
+        )
+        .arg(
+            Arg::with_name("lives")
+                .short('l')
+                .long("lives")
+                .value_name("LIVES")
+                .requires("endless")
+                .validator(|x| match x.trim().parse::<u32>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(String::from("Failed to parse lives. Please give a positive u32 integer.")),
+                })
+                .about("When enabled in Endless mode, you have this many lives, and the game ends when they run out.  Difficulty ramps up as you go."),
+        )
+        .arg(
+            Arg::with_name("life-cost")
+                .long("life-cost")
+                .value_name("errors | slow | both")
+                .possible_values(&["errors", "slow", "both"])
+                .requires("lives")
+                .about("What costs a life with --lives: errors, slow words, or both.  Defaults to both."),
+        )
+        .arg(
+            Arg::with_name("slow")
+                .long("slow")
+                .value_name("SECS")
+                .requires("lives")
+                .validator(seconds)
+                .about("A word taking longer than this costs a life with --lives.  Defaults to 5."),
         )
         .get_matches();
 
-    // .unwrap() is acceptable for this purpose because CLAP requires the argument input-file.
-    let words = read_file(matches.value_of("input-file").unwrap())?;
-    let word_sets = parse_to_sets(words);
+    if let ("packs", Some(packs)) = matches.subcommand() {
+        if let ("list", _) = packs.subcommand() {
+            pack::list();
+        }
+        return Ok(());
+    }
+
+    let matching = Matching {
+        normalization: match matches.value_of("normalize") {
+            Some("nfc") => Normalization::Nfc,
+            Some("nfd") => Normalization::Nfd,
+            _ => Normalization::None,
+        },
+        ignore_accents: matches.is_present("ignore-accents"),
+    };
+    let mut sources: Vec<Source> = Vec::new();
+    let words: Vec<Word> = match matches.value_of("pack") {
+        Some(name) => prepare_words(
+            pack::find(name)?
+                .words
+                .into_iter()
+                .map(|word| word.into_word())
+                .collect(),
+            &matches,
+            &matching,
+        ),
+        None => {
+            for arg in matches.values_of("input-file").into_iter().flatten() {
+                sources.extend(words::sources(arg, false)?);
+            }
+            for arg in matches.values_of("from-text").into_iter().flatten() {
+                sources.extend(words::sources(arg, true)?);
+            }
+            let files = sources
+                .iter()
+                .map(Source::read)
+                .collect::<io::Result<Vec<Vec<u8>>>>()?;
+            let read = || {
+                let mut words: Vec<Word> = Vec::new();
+                for (source, bytes) in sources.iter().zip(&files) {
+                    let mut list = source.words(bytes, matches.is_present("lowercase"));
+                    if sources.len() > 1 || source.weight.is_some() {
+                        words::weigh(&mut list, source.weight.unwrap_or(1.));
+                    }
+                    words.extend(list);
+                }
+                prepare_words(words, &matches, &matching)
+            };
+
+            if matches.is_present("index") {
+                // A file read with --from-text gives different words than as a wordlist.
+                let weights: Vec<String> = sources
+                    .iter()
+                    .map(|source| format!("{:?}", source.weight))
+                    .collect();
+                let texts: Vec<String> = sources
+                    .iter()
+                    .map(|source| source.text.to_string())
+                    .collect();
+                let key = index::key(
+                    &files,
+                    &format!(
+                        "{} weights={} texts={}",
+                        word_options(&matches),
+                        weights.join(","),
+                        texts.join(",")
+                    ),
+                );
+                match index::load(Path::new(index::INDEX_DIR), key) {
+                    Some(words) => words,
+                    None => {
+                        let words = read();
+                        if let Err(error) = index::save(Path::new(index::INDEX_DIR), key, &words) {
+                            eprintln!("The index was not saved.  Error: {:?}", error);
+                        }
+                        words
+                    }
+                }
+            } else {
+                read()
+            }
+        }
+    };
+    // Weighting a list only makes sense if words are picked by weight.
+    let weighted =
+        matches.is_present("weighted") || sources.iter().any(|source| source.weight.is_some());
+    let word_sets = WordSets::new(words, weighted);
     let options = options(&matches);
     let mode = if matches.is_present("time-attack") {
         Mode::TimeAttack
@@ -617,12 +1841,71 @@ fn main() -> io::Result<()> {
         Mode::Endless
     } else if matches.is_present("race") {
         Mode::Race
+    } else if matches.is_present("timed") {
+        Mode::Timed
+    } else if matches.is_present("drill") {
+        Mode::Drill
+    } else if matches.is_present("vocab") {
+        Mode::Vocab
     } else {
         // I like the explicitness of seeing all match arms like this, hence the unreachable!().
         unreachable!();
     };
+    // Like --skip-errors, moving on from a wrong word would make Time Attack too easy.
+    if mode == Mode::TimeAttack && options.on_error == Policy::Continue {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Time Attack can't be played with --on-error continue.",
+        ));
+    }
+    let player = matches.value_of("player").unwrap_or("default");
+    let missed = Missed::load(player);
+    let today = vocab::today();
+    let mut schedule = Schedule::load(player);
+    let vocab = match matches.value_of("vocab") {
+        Some(filename) => Some(Vocab::read(filename)?.normalize(|word| matching.normalize(word))),
+        None => None,
+    };
+    let deck = match mode {
+        Mode::Drill if missed.words.is_empty() => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "No missed words to drill for player \"{}\".  Play a game first.",
+                    player
+                ),
+            ))
+        }
+        Mode::Drill => Some(RefCell::new(Deck::new(missed.drill(&mut thread_rng())))),
+        Mode::Vocab => {
+            let vocab = vocab.as_ref().expect("Vocab mode without a vocab file.");
+            let session = schedule.session(
+                vocab,
+                matches
+                    .value_of("new")
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(10),
+                today,
+            );
+            if session.is_empty() {
+                match schedule.next_due(vocab, today) {
+                    Some(days) => println!(
+                        "Nothing to review today.  The next review is in {}.",
+                        plural(days, "day")
+                    ),
+                    None => println!("No words in {}.", matches.value_of("vocab").unwrap_or("")),
+                }
+                return Ok(());
+            }
+            Some(RefCell::new(Deck::new(session)))
+        }
+        _ => None,
+    };
     // count better be a Some() after all that bullshit I did up above.
-    let count = if mode == Mode::Race {
+    let count = if let Some(deck) = &deck {
+        // A drill goes through every missed word once, and a vocab session through every word due.
+        Some(vec![deck.borrow().len() as u32])
+    } else if mode == Mode::Race {
         Some(
             matches
                 .values_of("race")
@@ -633,13 +1916,125 @@ fn main() -> io::Result<()> {
     } else {
         None
     };
+    let duration = if mode == Mode::Timed {
+        Some(Duration::from_secs(
+            matches
+                .value_of("duration")
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .unwrap_or(60),
+        ))
+    } else {
+        None
+    };
+    let bank = if options.accumulate {
+        let secs = |name: &str, default: f32| {
+            matches
+                .value_of(name)
+                .and_then(|value| value.trim().parse::<f32>().ok())
+                .unwrap_or(default)
+        };
+        Some(TimeBank {
+            cap: secs("cap", 30.),
+            per_word: secs("bonus-word", 1.),
+            per_char: secs("bonus-char", 0.),
+        })
+    } else {
+        None
+    };
+
+    let survival = match matches.value_of("lives") {
+        Some(lives) => {
+            let cost = matches.value_of("life-cost").unwrap_or("both");
+            Some(Survival {
+                lives: lives.trim().parse::<u32>().unwrap_or(3),
+                on_error: cost != "slow",
+                slow: match cost {
+                    "errors" => None,
+                    _ => matches
+                        .value_of("slow")
+                        .and_then(|value| value.trim().parse::<f32>().ok())
+                        .or(Some(5.)),
+                },
+            })
+        }
+        None => None,
+    };
+
+    // Each mode keeps its old feel by default: Time Attack and survival ramp up in stages,
+    // while Race and Endless pick anything in range.  Timed sticks to the easiest words, so its
+    // WPM compares with other typing tests rather than being dragged down by long words.
+    let progression = match matches
+        .value_of("progression")
+        .and_then(Progression::from_name)
+    {
+        Some(progression) => progression,
+        None if mode == Mode::TimeAttack || survival.is_some() => Progression::Count,
+        None if mode == Mode::Timed => Progression::Fixed,
+        None => Progression::Random,
+    };
 
     let game: Game = Game {
         mode,
         word_sets,
         options,
         count,
+        duration,
+        bank,
+        survival,
+        progression,
+        lookahead: matches
+            .value_of("lookahead")
+            .and_then(|value| value.trim().parse::<usize>().ok()),
+        matching,
+        messages: Messages::load(matches.value_of("messages"))?,
+        deck,
+        vocab,
+        missed: RefCell::new(missed),
+        retire: matches
+            .value_of("retire")
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .unwrap_or(3),
+        start: Start {
+            intro: match matches.is_present("no-countdown") {
+                true => 0.,
+                false => matches
+                    .value_of("intro")
+                    .and_then(|value| value.trim().parse::<f32>().ok())
+                    .unwrap_or(4.),
+            },
+            countdown: match matches.is_present("no-countdown") {
+                true => 0,
+                false => matches
+                    .value_of("countdown")
+                    .and_then(|value| value.trim().parse::<u32>().ok())
+                    .unwrap_or(3),
+            },
+            wait: matches.is_present("wait"),
+        },
+    };
+
+    // Only the difficulties the game can reach need words.
+    let (low, high) = match game.mode {
+        Mode::Race => {
+            let (_, low, high) = race_params(&game);
+            (low, high)
+        }
+        _ if game.progression == Progression::Fixed => (1, 1),
+        _ => (1, 4),
     };
+    // --race checks each value on its own, so the range as a whole is checked here.
+    if low > high || low < 1 || high > 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Race difficulties must go from low to high, between 1 and 4.  Got {} to {}.",
+                low, high
+            ),
+        ));
+    }
+    if game.deck.is_none() {
+        game.word_sets.check(low, high)?;
+    }
 
     let scores = play(&game).unwrap_or(Score::default());
     if scores != Score::default() {
@@ -647,6 +2042,37 @@ fn main() -> io::Result<()> {
             Ok(()) => println!("\nYour score has been recorded.  Thanks for playing!"),
             Err(error) => println!("Your score was not recorded.  Error: {:?}", error),
         }
+        if game.mode == Mode::Race {
+            let (word_count, low, high) = race_params(&game);
+            match ghost::save(word_count, low, high, &scores.splits) {
+                Ok(true) => println!("New personal best!  Your ghost will race you next time."),
+                Ok(false) => (),
+                Err(error) => println!("Your ghost was not recorded.  Error: {:?}", error),
+            }
+        }
+
+        if game.mode == Mode::Vocab {
+            for stat in &scores.words {
+                schedule.grade(&stat.word, stat.quality(), today);
+            }
+            if let Err(error) = schedule.save() {
+                println!("Your reviews were not recorded.  Error: {:?}", error);
+            }
+            if let Some(days) = game
+                .vocab
+                .as_ref()
+                .and_then(|vocab| schedule.next_due(vocab, today))
+            {
+                println!("Next review in {}.", plural(days, "day"));
+            }
+        }
+    }
+
+    // Misses count even if the game ended without a score.
+    if game.mode != Mode::Vocab {
+        if let Err(error) = game.missed.borrow().save() {
+            println!("Your missed words were not recorded.  Error: {:?}", error);
+        }
     }
 
     Ok(())
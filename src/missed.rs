@@ -0,0 +1,111 @@
+// Missed words: every word a player has typed wrong, kept until they've typed it right enough times.
+// Stored in missed.txt, one line per word, as "PLAYER WORD CLEAN", where CLEAN is how many times
+// in a row the word has been typed without a wrong letter since it was last missed.
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+const MISSED_FILE: &str = "missed.txt";
+
+pub struct Missed {
+    player: String,
+    pub words: Vec<(String, u32)>, // Each word and its clean typings.
+}
+
+fn read_missed() -> Vec<(String, String, u32)> {
+    match OpenOptions::new().read(true).open(Path::new(MISSED_FILE)) {
+        Ok(file) => BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let player = fields.next()?.to_string();
+                let word = fields.next()?.to_string();
+                let clean = fields.next()?.parse().ok()?;
+                Some((player, word, clean))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+impl Missed {
+    pub fn load(player: &str) -> Missed {
+        Missed {
+            player: player.to_string(),
+            words: read_missed()
+                .into_iter()
+                .filter(|(owner, _, _)| owner == player)
+                .map(|(_, word, clean)| (word, clean))
+                .collect(),
+        }
+    }
+
+    // Adds a word typed wrong, or starts its clean typings over if it's already there.
+    pub fn miss(&mut self, word: &str) {
+        match self.words.iter_mut().find(|(missed, _)| missed == word) {
+            Some((_, clean)) => *clean = 0,
+            None => self.words.push((word.to_string(), 0)),
+        }
+    }
+
+    // Counts a clean typing of a word, and drops it once it has `retire` of them.
+    pub fn clean(&mut self, word: &str, retire: u32) {
+        if let Some((_, clean)) = self.words.iter_mut().find(|(missed, _)| missed == word) {
+            *clean += 1;
+        }
+        self.words.retain(|(_, clean)| *clean < retire);
+    }
+
+    // The words to drill, in a random order but with those typed clean the fewest times first.
+    pub fn drill<R: Rng>(&self, rng: &mut R) -> Vec<String> {
+        let mut words = self.words.clone();
+        words.shuffle(rng);
+        words.sort_by_key(|(_, clean)| *clean);
+        words.into_iter().map(|(word, _)| word).collect()
+    }
+
+    // Writes the player's words back, keeping everyone else's.
+    pub fn save(&self) -> io::Result<()> {
+        let mut contents = String::new();
+        for (player, word, clean) in read_missed() {
+            if player != self.player {
+                contents.push_str(&format!("{} {} {}\n", player, word, clean));
+            }
+        }
+        for (word, clean) in &self.words {
+            contents.push_str(&format!("{} {} {}\n", self.player, word, clean));
+        }
+        fs::write(MISSED_FILE, contents)
+    }
+}
+
+// The words of a drill or vocab session, dealt in order.  Once all have been dealt they're shuffled
+// and dealt again, so the game can queue up words past the end of the session.
+pub struct Deck {
+    words: Vec<String>,
+    left: Vec<String>,
+}
+
+impl Deck {
+    pub fn new(words: Vec<String>) -> Deck {
+        Deck {
+            left: words.iter().rev().cloned().collect(),
+            words,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn draw<R: Rng>(&mut self, rng: &mut R) -> Option<String> {
+        if self.left.is_empty() {
+            self.left = self.words.clone();
+            self.left.shuffle(rng);
+        }
+        self.left.pop()
+    }
+}
@@ -0,0 +1,194 @@
+// Word packs: wordlists with a header describing them, installed in the "packs" directory.
+// A pack looks like this:
+//
+//     name: French
+//     language: fr
+//     license: CC0-1.0
+//     ---
+//     être 12
+//     maison 85 2
+//
+// The header is "key: value" lines ending with "---".  After it, each line is a word, then optionally
+// its frequency rank (1 is the most common word), then optionally a difficulty to put it in instead
+// of the one its length gives it.
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::words::Word;
+
+const PACKS_DIR: &str = "packs";
+const PACK_EXTENSION: &str = "pack";
+
+pub struct PackWord {
+    pub text: String,
+    pub rank: Option<u32>,
+    pub difficulty: Option<u32>,
+}
+
+impl PackWord {
+    // Ranks become frequencies by Zipf's law: the word ranked n is about 1/n as common as the first.
+    pub fn into_word(self) -> Word {
+        Word {
+            text: self.text,
+            frequency: self.rank.map(|rank| 1. / rank.max(1) as f64),
+            difficulty: self.difficulty,
+        }
+    }
+}
+
+pub struct Pack {
+    pub name: String,
+    pub language: String,
+    pub license: String,
+    pub path: PathBuf,
+    pub words: Vec<PackWord>,
+}
+
+fn invalid(path: &Path, line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}:{}: {}", path.display(), line, message),
+    )
+}
+
+// Reads a pack.  Words come out sorted by rank, with unranked words last in file order.
+pub fn read_pack(path: &Path) -> io::Result<Pack> {
+    let file_object = OpenOptions::new().read(true).open(path)?;
+    let mut pack = Pack {
+        name: path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        language: String::from("unknown"),
+        license: String::from("unknown"),
+        path: path.to_path_buf(),
+        words: Vec::new(),
+    };
+
+    let mut in_header = true;
+    for (i, line) in BufReader::new(file_object).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if in_header {
+            if line == "---" {
+                in_header = false;
+            } else if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim().to_string();
+                match key.trim() {
+                    "name" => pack.name = value,
+                    "language" => pack.language = value,
+                    "license" => pack.license = value,
+                    _ => (), // Unknown keys are allowed, for packs made by newer versions.
+                }
+            } else if !line.is_empty() {
+                return Err(invalid(path, i + 1, "expected \"key: value\" or \"---\""));
+            }
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let text = match fields.next() {
+            Some(text) => text.to_string(),
+            None => continue,
+        };
+        let mut number = |what: &str| match fields.next() {
+            Some(field) => field
+                .parse::<u32>()
+                .map(Some)
+                .map_err(|_| invalid(path, i + 1, &format!("{} must be a number", what))),
+            None => Ok(None),
+        };
+        let rank = number("rank")?;
+        let difficulty = number("difficulty")?;
+        if let Some(d) = difficulty {
+            if !(1..=4).contains(&d) {
+                return Err(invalid(path, i + 1, "difficulty must be from 1 to 4"));
+            }
+        }
+        pack.words.push(PackWord {
+            text,
+            rank,
+            difficulty,
+        });
+    }
+    if in_header {
+        return Err(invalid(path, 1, "no \"---\" line ends the header"));
+    }
+
+    // sort_by_key is stable, so words without a rank keep their order.
+    pack.words.sort_by_key(|word| word.rank.unwrap_or(u32::MAX));
+    Ok(pack)
+}
+
+// Every pack in the packs directory.  Packs that fail to load are reported and left out.
+pub fn installed() -> Vec<Pack> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(PACKS_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == PACK_EXTENSION))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+
+    paths
+        .iter()
+        .filter_map(|path| match read_pack(path) {
+            Ok(pack) => Some(pack),
+            Err(error) => {
+                eprintln!("Skipping pack {}: {}", path.display(), error);
+                None
+            }
+        })
+        .collect()
+}
+
+// Finds an installed pack by its name or file name, ignoring case.
+pub fn find(name: &str) -> io::Result<Pack> {
+    let wanted = name.to_lowercase();
+    installed()
+        .into_iter()
+        .find(|pack| {
+            pack.name.to_lowercase() == wanted
+                || pack
+                    .path
+                    .file_stem()
+                    .is_some_and(|stem| stem.to_string_lossy().to_lowercase() == wanted)
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "No pack named \"{}\" in the {} directory.  Run \"packs list\" to see installed packs.",
+                    name, PACKS_DIR
+                ),
+            )
+        })
+}
+
+// Prints a table of installed packs, for "packs list".
+pub fn list() {
+    let packs = installed();
+    if packs.is_empty() {
+        println!(
+            "No packs installed.  Put .{} files in the {} directory.",
+            PACK_EXTENSION, PACKS_DIR
+        );
+        return;
+    }
+    println!(
+        "{:<20} {:<10} {:>7}  {:<16} FILE",
+        "NAME", "LANGUAGE", "WORDS", "LICENSE"
+    );
+    for pack in packs {
+        println!(
+            "{:<20} {:<10} {:>7}  {:<16} {}",
+            pack.name,
+            pack.language,
+            pack.words.len(),
+            pack.license,
+            pack.path.display()
+        );
+    }
+}
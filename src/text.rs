@@ -0,0 +1,118 @@
+// Measuring and comparing words by what the player sees, not by bytes.
+// "é" may be one code point or an "e" followed by a combining accent, and either way it's one
+// letter to type, so lengths count grapheme clusters and comparisons can normalize first.
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub enum Normalization {
+    #[default]
+    None,
+    Nfc,
+    Nfd,
+}
+
+// How typed words are matched against the words on screen.
+#[derive(Clone, Copy, Default)]
+pub struct Matching {
+    pub normalization: Normalization,
+    pub ignore_accents: bool,
+}
+
+impl Matching {
+    // Puts a word in the form used for display and comparison.
+    pub fn normalize(&self, word: &str) -> String {
+        match self.normalization {
+            Normalization::None => word.to_string(),
+            Normalization::Nfc => word.nfc().collect(),
+            Normalization::Nfd => word.nfd().collect(),
+        }
+    }
+
+    // Whether `typed` counts as typing `target`.
+    pub fn matches(&self, typed: &str, target: &str) -> bool {
+        if self.ignore_accents {
+            strip_accents(typed) == strip_accents(target)
+        } else if self.normalization == Normalization::None {
+            typed == target
+        } else {
+            self.normalize(typed) == self.normalize(target)
+        }
+    }
+}
+
+// Decomposes a word and drops the combining marks, so "café" becomes "cafe".
+fn strip_accents(word: &str) -> String {
+    word.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .nfc()
+        .collect()
+}
+
+// Number of letters in a word, as the player would count them.
+pub fn length(word: &str) -> usize {
+    word.graphemes(true).count()
+}
+
+// Removes the last letter of a word, including any accents on it.
+pub fn pop_grapheme(word: &mut String) {
+    if let Some((start, _)) = word.grapheme_indices(true).next_back() {
+        word.truncate(start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSED: &str = "caf\u{e9}";
+    const DECOMPOSED: &str = "cafe\u{301}";
+
+    fn matching(normalization: Normalization, ignore_accents: bool) -> Matching {
+        Matching {
+            normalization,
+            ignore_accents,
+        }
+    }
+
+    #[test]
+    fn normalization_matches_either_form() {
+        assert!(!matching(Normalization::None, false).matches(DECOMPOSED, COMPOSED));
+        assert!(matching(Normalization::Nfc, false).matches(DECOMPOSED, COMPOSED));
+        assert!(matching(Normalization::Nfd, false).matches(COMPOSED, DECOMPOSED));
+        assert_eq!(
+            matching(Normalization::Nfc, false).normalize(DECOMPOSED),
+            COMPOSED
+        );
+        assert_eq!(
+            matching(Normalization::Nfd, false).normalize(COMPOSED),
+            DECOMPOSED
+        );
+    }
+
+    #[test]
+    fn ignore_accents_matches_plain_letters() {
+        let matching = matching(Normalization::None, true);
+        assert!(matching.matches("cafe", COMPOSED));
+        assert!(matching.matches("cafe", DECOMPOSED));
+        assert!(matching.matches(COMPOSED, "cafe"));
+        assert!(!matching.matches("cafes", COMPOSED));
+    }
+
+    #[test]
+    fn accents_are_part_of_the_letter() {
+        assert_eq!(length(COMPOSED), 4);
+        assert_eq!(length(DECOMPOSED), 4);
+
+        let mut word = DECOMPOSED.to_string();
+        pop_grapheme(&mut word);
+        assert_eq!(word, "caf");
+        pop_grapheme(&mut word);
+        assert_eq!(word, "ca");
+
+        let mut word = String::new();
+        pop_grapheme(&mut word);
+        assert_eq!(word, "");
+    }
+}
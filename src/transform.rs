@@ -0,0 +1,75 @@
+// Dressing up words drawn from the wordlist with capitals, punctuation and numbers.
+// Wordlists are lowercase letters, so without this the game never has you reach for Shift,
+// the number row or symbols.
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// Commas and periods are the most common, so they come up more often.
+const PUNCTUATION: &[&str] = &[",", ",", ",", ".", ".", ".", ";", ":", "!", "?"];
+const BRACKETS: &[(&str, &str)] = &[("\"", "\""), ("(", ")"), ("'", "'")];
+
+// The chance of each change happening to a word, from 0 to 1.
+#[derive(Default)]
+pub struct Transform {
+    pub capitals: f32,
+    pub punctuation: f32,
+    pub numbers: f32,
+}
+
+impl Transform {
+    // The word as it's shown, and the word it was made from, or None if a number took its place.
+    pub fn apply<R: Rng>(&self, word: String, rng: &mut R) -> (String, Option<String>) {
+        if rng.gen::<f32>() < self.numbers {
+            return (number(rng), None);
+        }
+        let mut shown = word.clone();
+        if rng.gen::<f32>() < self.capitals {
+            shown = capitalize(&shown);
+        }
+        if rng.gen::<f32>() < self.punctuation {
+            shown = punctuate(shown, rng);
+        }
+        (shown, Some(word))
+    }
+
+    // The options used, for the mode string in the score file, like " --capitals=0.25".
+    pub fn flags(&self) -> String {
+        [
+            ("capitals", self.capitals),
+            ("punctuation", self.punctuation),
+            ("numbers", self.numbers),
+        ]
+        .iter()
+        .filter(|(_, chance)| *chance > 0.)
+        .map(|(name, chance)| format!(" --{}={}", name, chance))
+        .collect()
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Ends the word with a punctuation mark, or now and then puts it in quotes or brackets.
+fn punctuate<R: Rng>(word: String, rng: &mut R) -> String {
+    if rng.gen_range(0, 5) == 0 {
+        let (open, close) = BRACKETS.choose(rng).unwrap_or(&("\"", "\""));
+        format!("{}{}{}", open, word, close)
+    } else {
+        word + PUNCTUATION.choose(rng).unwrap_or(&".")
+    }
+}
+
+// A number of 1 to 4 digits, in place of a word.
+fn number<R: Rng>(rng: &mut R) -> String {
+    let digits = rng.gen_range(1, 5);
+    let low = match digits {
+        1 => 0,
+        _ => 10u32.pow(digits - 1),
+    };
+    rng.gen_range(low, 10u32.pow(digits)).to_string()
+}
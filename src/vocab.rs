@@ -0,0 +1,314 @@
+// Vocabulary practice: the game shows a definition or translation, and the player types the word.
+// A vocab file has one word per line, then a tab or " = ", then its definition:
+//
+//     maison = house
+//     chien	dog
+//
+// When each word is due again is worked out with SM-2, the algorithm from SuperMemo: words you
+// know well come back after longer and longer intervals, and words you miss come back the next day.
+// Reviews are stored in reviews.txt, one line per word, as "PLAYER WORD EASE INTERVAL REPETITIONS DUE",
+// where INTERVAL is in days and DUE is the day number (days since 1970) the word is next due.
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::words;
+
+const REVIEWS_FILE: &str = "reviews.txt";
+
+// Definitions by word, in the order they're in the file.
+pub struct Vocab {
+    pub words: Vec<String>,
+    definitions: HashMap<String, String>,
+}
+
+impl Vocab {
+    // Lines without a definition are reported and skipped, like bad lines in a wordlist.
+    pub fn read(filename: &str) -> io::Result<Vocab> {
+        let mut vocab = Vocab {
+            words: Vec::new(),
+            definitions: HashMap::new(),
+        };
+        let mut problems: Vec<(usize, String)> = Vec::new();
+        for (i, line) in fs::read_to_string(filename)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (word, definition) = match line.split_once('\t').or_else(|| line.split_once(" = "))
+            {
+                Some((word, definition)) => (word.trim(), definition.trim()),
+                None => {
+                    problems.push((
+                        i + 1,
+                        String::from("expected a word, a tab or \" = \", and a definition"),
+                    ));
+                    continue;
+                }
+            };
+            if word.contains(char::is_whitespace) {
+                problems.push((i + 1, String::from("words can't have spaces")));
+                continue;
+            }
+            if !vocab.definitions.contains_key(word) {
+                vocab.words.push(word.to_string());
+            }
+            vocab
+                .definitions
+                .insert(word.to_string(), definition.to_string());
+        }
+        words::report(filename, &problems);
+        Ok(vocab)
+    }
+
+    pub fn definition(&self, word: &str) -> &str {
+        self.definitions
+            .get(word)
+            .map(String::as_str)
+            .unwrap_or("?")
+    }
+
+    // Puts every word in the form used for display and comparison.
+    pub fn normalize<F: Fn(&str) -> String>(self, normalize: F) -> Vocab {
+        Vocab {
+            words: self.words.iter().map(|word| normalize(word)).collect(),
+            definitions: self
+                .definitions
+                .into_iter()
+                .map(|(word, definition)| (normalize(&word), definition))
+                .collect(),
+        }
+    }
+}
+
+// Where one word is in its reviews.
+#[derive(Clone, Copy)]
+struct Review {
+    ease: f32,        // How fast the interval grows.  Starts at 2.5, and never goes under 1.3.
+    interval: u32,    // Days until the next review.
+    repetitions: u32, // Reviews passed in a row.
+    due: u64,
+}
+
+impl Review {
+    fn new() -> Review {
+        Review {
+            ease: 2.5,
+            interval: 0,
+            repetitions: 0,
+            due: 0,
+        }
+    }
+
+    // SM-2: `quality` is how well the word was recalled, from 0 (not at all) to 5 (perfectly).
+    fn grade(&mut self, quality: u32, today: u64) {
+        if quality >= 3 {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as f32 * self.ease).round() as u32,
+            };
+            self.repetitions += 1;
+        } else {
+            self.repetitions = 0;
+            self.interval = 1;
+        }
+        let miss = (5 - quality.min(5)) as f32;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(1.3);
+        self.due = today + self.interval as u64;
+    }
+}
+
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs() / 86400)
+        .unwrap_or_default()
+}
+
+// A player's reviews, by word.
+pub struct Schedule {
+    player: String,
+    reviews: HashMap<String, Review>,
+}
+
+fn read_reviews() -> Vec<(String, String, Review)> {
+    match OpenOptions::new().read(true).open(Path::new(REVIEWS_FILE)) {
+        Ok(file) => BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                match fields[..] {
+                    [player, word, ease, interval, repetitions, due] => Some((
+                        player.to_string(),
+                        word.to_string(),
+                        Review {
+                            ease: ease.parse().ok()?,
+                            interval: interval.parse().ok()?,
+                            repetitions: repetitions.parse().ok()?,
+                            due: due.parse().ok()?,
+                        },
+                    )),
+                    _ => None,
+                }
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+impl Schedule {
+    pub fn load(player: &str) -> Schedule {
+        Schedule {
+            player: player.to_string(),
+            reviews: read_reviews()
+                .into_iter()
+                .filter(|(owner, _, _)| owner == player)
+                .map(|(_, word, review)| (word, review))
+                .collect(),
+        }
+    }
+
+    // The words to review today: those that are due, the most overdue first, then up to
+    // `new` words that haven't been reviewed yet, in the order they're in the vocab file.
+    pub fn session(&self, vocab: &Vocab, new: usize, today: u64) -> Vec<String> {
+        let mut due: Vec<(&String, u64)> = vocab
+            .words
+            .iter()
+            .filter_map(|word| self.reviews.get(word).map(|review| (word, review.due)))
+            .filter(|(_, due)| *due <= today)
+            .collect();
+        due.sort_by_key(|(_, due)| *due);
+        due.into_iter()
+            .map(|(word, _)| word.clone())
+            .chain(
+                vocab
+                    .words
+                    .iter()
+                    .filter(|word| !self.reviews.contains_key(*word))
+                    .take(new)
+                    .cloned(),
+            )
+            .collect()
+    }
+
+    // Days until the next word in `vocab` is due, if any have been reviewed.
+    pub fn next_due(&self, vocab: &Vocab, today: u64) -> Option<u64> {
+        vocab
+            .words
+            .iter()
+            .filter_map(|word| self.reviews.get(word))
+            .map(|review| review.due.saturating_sub(today))
+            .min()
+    }
+
+    pub fn grade(&mut self, word: &str, quality: u32, today: u64) {
+        self.reviews
+            .entry(word.to_string())
+            .or_insert_with(Review::new)
+            .grade(quality, today);
+    }
+
+    // Writes the player's reviews back, keeping everyone else's.
+    pub fn save(&self) -> io::Result<()> {
+        let mut contents = String::new();
+        let line = |player: &str, word: &str, review: &Review| {
+            format!(
+                "{} {} {:.2} {} {} {}\n",
+                player, word, review.ease, review.interval, review.repetitions, review.due
+            )
+        };
+        for (player, word, review) in read_reviews() {
+            if player != self.player {
+                contents.push_str(&line(&player, &word, &review));
+            }
+        }
+        let mut words: Vec<&String> = self.reviews.keys().collect();
+        words.sort();
+        for word in words {
+            contents.push_str(&line(&self.player, word, &self.reviews[word]));
+        }
+        fs::write(REVIEWS_FILE, contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocab(words: &[&str]) -> Vocab {
+        Vocab {
+            words: words.iter().map(|word| word.to_string()).collect(),
+            definitions: words
+                .iter()
+                .map(|word| (word.to_string(), String::from("?")))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn intervals_grow_by_ease() {
+        let mut review = Review::new();
+        review.grade(4, 100);
+        assert_eq!((review.interval, review.due), (1, 101));
+        review.grade(4, 101);
+        assert_eq!((review.interval, review.due), (6, 107));
+        review.grade(4, 107);
+        assert_eq!(review.interval, (6. * review.ease).round() as u32);
+        assert_eq!(review.repetitions, 3);
+    }
+
+    #[test]
+    fn perfect_recall_raises_ease() {
+        let mut review = Review::new();
+        review.grade(5, 0);
+        assert!((review.ease - 2.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ease_never_goes_under_floor() {
+        let mut review = Review::new();
+        for day in 0..20 {
+            review.grade(0, day);
+        }
+        assert!((review.ease - 1.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn miss_starts_over() {
+        let mut review = Review::new();
+        review.grade(5, 0);
+        review.grade(5, 1);
+        review.grade(2, 7);
+        assert_eq!(review.repetitions, 0);
+        assert_eq!((review.interval, review.due), (1, 8));
+    }
+
+    #[test]
+    fn session_puts_due_words_first() {
+        let vocab = vocab(&["un", "deux", "trois", "quatre", "cinq"]);
+        let mut schedule = Schedule {
+            player: String::from("test"),
+            reviews: HashMap::new(),
+        };
+        let review = |due| Review {
+            due,
+            ..Review::new()
+        };
+        schedule.reviews.insert(String::from("deux"), review(10));
+        schedule.reviews.insert(String::from("quatre"), review(5));
+        schedule.reviews.insert(String::from("cinq"), review(20)); // Not due yet.
+
+        // Most overdue first, then new words in file order, up to `new` of them.
+        assert_eq!(
+            schedule.session(&vocab, 1, 12),
+            vec!["quatre", "deux", "un"]
+        );
+        assert_eq!(schedule.next_due(&vocab, 12), Some(0));
+        assert_eq!(schedule.session(&vocab, 0, 4), Vec::<String>::new());
+        assert_eq!(schedule.next_due(&vocab, 4), Some(1));
+    }
+}
@@ -0,0 +1,487 @@
+// Reading wordlists and sorting their words into difficulties.
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use unicode_normalization::char::is_combining_mark;
+
+use crate::difficulty::bucket_for;
+use crate::text::length;
+
+#[derive(Clone)]
+pub struct Word {
+    pub text: String,
+    pub frequency: Option<f64>, // How common the word is, from a count or a rank.  Only relative sizes matter.
+    pub difficulty: Option<u32>, // Overrides the difficulty given by length.
+}
+
+impl Word {
+    pub fn new(text: String) -> Word {
+        Word {
+            text,
+            frequency: None,
+            difficulty: None,
+        }
+    }
+}
+
+// Words sorted into difficulties 1 to 4.
+pub struct WordSets {
+    sets: HashMap<u32, Vec<Word>>,
+    weighted: bool, // Pick common words more often, by their frequency.
+}
+
+impl WordSets {
+    pub fn new(mut words: Vec<Word>, weighted: bool) -> WordSets {
+        if weighted {
+            // Words without a frequency are treated as the rarest ones that have one.
+            let rarest = words
+                .iter()
+                .filter_map(|word| word.frequency)
+                .fold(f64::INFINITY, f64::min);
+            let rarest = if rarest.is_finite() { rarest } else { 1. };
+            for word in words.iter_mut() {
+                word.frequency = word.frequency.or(Some(rarest));
+            }
+        }
+        assign_difficulties(&mut words);
+        WordSets {
+            sets: parse_to_sets(words),
+            weighted,
+        }
+    }
+
+    // Makes sure difficulties `low` to `high` have words to play with.  If only some are empty, the
+    // nearest difficulty with words stands in for them, so that's a warning rather than an error.
+    pub fn check(&self, low: u32, high: u32) -> io::Result<()> {
+        let empty: Vec<u32> = (low..=high)
+            .filter(|difficulty| self.sets.get(difficulty).is_none_or(Vec::is_empty))
+            .collect();
+        if empty.len() as u32 == high - low + 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "No words for difficulty {} to {} ({} letters).",
+                    low,
+                    high,
+                    bucket_lengths(low, high)
+                ),
+            ));
+        }
+        for difficulty in empty {
+            eprintln!(
+                "No words for difficulty {} ({} letters); the nearest difficulty with words will be used instead.",
+                difficulty,
+                bucket_lengths(difficulty, difficulty)
+            );
+        }
+        Ok(())
+    }
+
+    // A random word of the given difficulty.  If that bucket is empty, the nearest one with words is used
+    // instead, so a wordlist without long words doesn't end the game.
+    pub fn pick<R: Rng>(&self, difficulty: u32, rng: &mut R) -> Option<String> {
+        for distance in 0..4 {
+            for bucket in &[
+                difficulty.checked_sub(distance),
+                difficulty.checked_add(distance),
+            ] {
+                let set = match bucket.and_then(|bucket| self.sets.get(&bucket)) {
+                    Some(set) => set,
+                    None => continue,
+                };
+                let word = match self.weighted {
+                    true => set
+                        .choose_weighted(rng, |word| word.frequency.unwrap_or(1.))
+                        .ok(),
+                    false => set.choose(rng),
+                };
+                if let Some(word) = word {
+                    return Some(word.text.clone());
+                }
+            }
+        }
+        None
+    }
+}
+
+// Which characters words may have.  Words with anything else are left out.
+pub enum Charset {
+    Letters, // Letters in any alphabet, with accents, apostrophes and hyphens.
+    Ascii,   // a to z, upper or lower case.
+    Any,
+    Only(Vec<char>),
+}
+
+impl Charset {
+    pub fn from_arg(arg: &str) -> Charset {
+        match arg {
+            "letters" => Charset::Letters,
+            "ascii" => Charset::Ascii,
+            "any" => Charset::Any,
+            chars => Charset::Only(chars.chars().collect()),
+        }
+    }
+
+    fn allows(&self, word: &str) -> bool {
+        match self {
+            Charset::Letters => word
+                .chars()
+                .all(|c| c.is_alphabetic() || is_combining_mark(c) || "'-\u{2019}".contains(c)),
+            Charset::Ascii => word.chars().all(|c| c.is_ascii_alphabetic()),
+            Charset::Any => true,
+            Charset::Only(chars) => word.chars().all(|c| chars.contains(&c)),
+        }
+    }
+}
+
+// Prints problems found in a file as "FILE:LINE: problem", up to a limit so a bad file can't flood the screen.
+pub fn report(filename: &str, problems: &[(usize, String)]) {
+    const SHOWN: usize = 10;
+    for (line, problem) in problems.iter().take(SHOWN) {
+        eprintln!("{}:{}: {}", filename, line, problem);
+    }
+    if problems.len() > SHOWN {
+        eprintln!("{}: ...and {} more.", filename, problems.len() - SHOWN);
+    }
+}
+
+// Reads a wordlist, one word per line.  A line may have a second column with how often the word
+// occurs (like "the 23135851162"), for --weighted and --top.
+// Blank lines and lines starting with '#' are skipped.  Lines that can't be read (bad UTF-8, a
+// frequency that isn't a number, extra columns) are reported with their line number and skipped.
+// `filename` is only for reporting problems.
+pub fn parse(filename: &str, bytes: &[u8]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut problems: Vec<(usize, String)> = Vec::new();
+
+    for (i, line) in bytes.split(|&byte| byte == b'\n').enumerate() {
+        let line = match std::str::from_utf8(line) {
+            Ok(line) => line,
+            Err(_) => {
+                problems.push((i + 1, String::from("not valid UTF-8")));
+                continue;
+            }
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let mut word = Word::new(fields[0].to_string());
+        match fields.len() {
+            1 => (),
+            2 => match fields[1].parse::<f64>() {
+                Ok(count) if count >= 0. => word.frequency = Some(count),
+                _ => {
+                    problems.push((
+                        i + 1,
+                        format!("frequency \"{}\" is not a number", fields[1]),
+                    ));
+                    continue;
+                }
+            },
+            _ => {
+                problems.push((
+                    i + 1,
+                    String::from("expected a word and at most a frequency"),
+                ));
+                continue;
+            }
+        }
+        words.push(word);
+    }
+
+    report(filename, &problems);
+    words
+}
+
+// Drops words with characters outside the charset, and merges repeated words into the first of
+// them.  A word in several lists is that much more common, so their frequencies add up.
+pub fn clean(words: Vec<Word>, charset: &Charset) -> Vec<Word> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut rejected: Vec<String> = Vec::new();
+    let mut kept: Vec<Word> = Vec::new();
+    for word in words {
+        if !charset.allows(&word.text) {
+            rejected.push(word.text);
+        } else if let Some(&i) = seen.get(&word.text) {
+            if let Some(frequency) = word.frequency {
+                kept[i].frequency = Some(kept[i].frequency.unwrap_or(0.) + frequency);
+            }
+        } else {
+            seen.insert(word.text.clone(), kept.len());
+            kept.push(word);
+        }
+    }
+
+    if !rejected.is_empty() {
+        eprintln!(
+            "Left out {} words with characters outside the charset, like \"{}\".",
+            rejected.len(),
+            rejected[0]
+        );
+    }
+    kept
+}
+
+// A wordlist to read, with how much its words should count for.
+pub struct Source {
+    pub path: PathBuf, // "-" for stdin.
+    pub weight: Option<f64>,
+    pub text: bool, // Any text to pick words out of, rather than a wordlist.
+}
+
+impl Source {
+    fn is_stdin(&self) -> bool {
+        self.path == Path::new("-")
+    }
+
+    // What to call the source in messages.
+    pub fn name(&self) -> String {
+        match self.is_stdin() {
+            true => String::from("<stdin>"),
+            false => self.path.display().to_string(),
+        }
+    }
+
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        let read = match self.is_stdin() {
+            true => {
+                let mut bytes = Vec::new();
+                io::stdin().read_to_end(&mut bytes).map(|_| bytes)
+            }
+            false => fs::read(&self.path),
+        };
+        read.map_err(|error| io::Error::new(error.kind(), format!("{}: {}", self.name(), error)))
+    }
+
+    // The words in the source's contents.  `lowercase` only applies to text.
+    pub fn words(&self, bytes: &[u8], lowercase: bool) -> Vec<Word> {
+        match self.text {
+            true => tokenize(&String::from_utf8_lossy(bytes), lowercase),
+            false => parse(&self.name(), bytes),
+        }
+    }
+}
+
+// Turns an input-file argument into the files it names.  It may be a file, "-" for stdin, a directory
+// (every file in it), or a glob like "lists/*.txt", and may end in "@WEIGHT" to weight its words.
+pub fn sources(arg: &str, text: bool) -> io::Result<Vec<Source>> {
+    let (pattern, weight) = match arg.rsplit_once('@') {
+        Some((pattern, weight)) => match weight.parse::<f64>() {
+            Ok(weight) if weight > 0. => (pattern, Some(weight)),
+            _ => (arg, None),
+        },
+        None => (arg, None),
+    };
+
+    let mut paths: Vec<PathBuf> = if pattern.contains(['*', '?', '[']) {
+        glob::glob(pattern)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error.to_string()))?
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .collect()
+    } else if pattern == "-" {
+        return Ok(vec![Source {
+            path: PathBuf::from(pattern),
+            weight,
+            text,
+        }]);
+    } else if Path::new(pattern).is_dir() {
+        fs::read_dir(pattern)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect()
+    } else {
+        vec![PathBuf::from(pattern)]
+    };
+    if paths.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No wordlists found in \"{}\".", pattern),
+        ));
+    }
+    paths.sort();
+
+    Ok(paths
+        .into_iter()
+        .map(|path| Source { path, weight, text })
+        .collect())
+}
+
+// Picks the words out of prose or source code, counting how often each comes up.  Anything that
+// isn't a letter splits words, except apostrophes inside them, so "don't" stays whole and
+// "read_file(path)" gives "read", "file" and "path".
+pub fn tokenize(text: &str, lowercase: bool) -> Vec<Word> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut words: Vec<Word> = Vec::new();
+    let tokens = text
+        .split(|c: char| {
+            !(c.is_alphabetic() || is_combining_mark(c) || c == '\'' || c == '\u{2019}')
+        })
+        .map(|token| token.trim_matches(|c| c == '\'' || c == '\u{2019}'))
+        .filter(|token| !token.is_empty());
+    for token in tokens {
+        let token = match lowercase {
+            true => token.to_lowercase(),
+            false => token.to_string(),
+        };
+        let count = counts.entry(token.clone()).or_insert_with(|| {
+            words.push(Word::new(token));
+            0
+        });
+        *count += 1;
+    }
+    for word in words.iter_mut() {
+        word.frequency = Some(counts[&word.text] as f64);
+    }
+    words
+}
+
+// Scales the frequencies of one wordlist's words so they average `weight`.  Lists count their words
+// in different ways (or not at all), so this puts them on one scale before they're merged: a word
+// from a list weighted 3 comes up about three times as often as a word from a list weighted 1.
+// Words without a frequency count as average.
+pub fn weigh(words: &mut [Word], weight: f64) {
+    let counts: Vec<f64> = words.iter().filter_map(|word| word.frequency).collect();
+    let mean = match counts.len() {
+        0 => 1.,
+        n => counts.iter().sum::<f64>() / n as f64,
+    };
+    for word in words.iter_mut() {
+        word.frequency = Some(match word.frequency {
+            Some(frequency) if mean > 0. => frequency / mean * weight,
+            _ => weight,
+        });
+    }
+}
+
+// Keeps the `n` most frequent words.  Words without a frequency count as rarer than any with one,
+// and otherwise keep their order, so a plain list sorted by frequency works too.
+pub fn keep_top(words: &mut Vec<Word>, n: usize) {
+    words.sort_by(|a, b| {
+        b.frequency
+            .unwrap_or(f64::NEG_INFINITY)
+            .total_cmp(&a.frequency.unwrap_or(f64::NEG_INFINITY))
+    });
+    words.truncate(n);
+}
+
+// The word lengths in difficulties `low` to `high`, like "3-8".
+fn bucket_lengths(low: u32, high: u32) -> String {
+    let shortest = 3 * low;
+    match high {
+        4 => format!("{}+", shortest),
+        _ => format!("{}-{}", shortest, 3 * high + 2),
+    }
+}
+
+// Gives every word without a difficulty the one its length puts it in, and drops words of fewer
+// than 3 letters, which don't go in any.  Done once, in a single pass, so that huge wordlists load
+// quickly and the result can be saved in an index.
+pub fn assign_difficulties(words: &mut Vec<Word>) {
+    words.retain_mut(|word| {
+        if word.difficulty.is_none() {
+            let letters = length(&word.text);
+            if letters < 3 {
+                return false;
+            }
+            word.difficulty = Some(bucket_for(letters));
+        }
+        true
+    });
+}
+
+fn parse_to_sets(words: Vec<Word>) -> HashMap<u32, Vec<Word>> {
+    // Every word has a difficulty by now, from assign_difficulties() or from a pack overriding it.
+    let mut word_sets: HashMap<u32, Vec<Word>> = (1..=4).map(|i| (i, Vec::new())).collect();
+    for word in words {
+        word_sets
+            .entry(word.difficulty.unwrap_or(1))
+            .or_default()
+            .push(word);
+    }
+    word_sets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_skips_bad_lines() {
+        let bytes = b"# a comment\ncat\ndog 12\n\nbird many\nfish 1 2\n\xff\nmoose 0.5\n";
+        let words = parse("test", bytes);
+        let parsed: Vec<(&str, Option<f64>)> = words
+            .iter()
+            .map(|word| (word.text.as_str(), word.frequency))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![("cat", None), ("dog", Some(12.)), ("moose", Some(0.5))]
+        );
+    }
+
+    #[test]
+    fn tokenize_counts_words_in_text() {
+        let words = tokenize("Don't read_file(path); 'The' the 'quoted' the.", false);
+        let tokens: Vec<(&str, Option<f64>)> = words
+            .iter()
+            .map(|word| (word.text.as_str(), word.frequency))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                ("Don't", Some(1.)),
+                ("read", Some(1.)),
+                ("file", Some(1.)),
+                ("path", Some(1.)),
+                ("The", Some(1.)),
+                ("the", Some(2.)),
+                ("quoted", Some(1.)),
+            ]
+        );
+
+        // Lowercasing merges "The" and "the" into one word.
+        let words = tokenize("The cat saw the dog", true);
+        let tokens: Vec<(&str, Option<f64>)> = words
+            .iter()
+            .map(|word| (word.text.as_str(), word.frequency))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                ("the", Some(2.)),
+                ("cat", Some(1.)),
+                ("saw", Some(1.)),
+                ("dog", Some(1.)),
+            ]
+        );
+    }
+
+    #[test]
+    fn sources_parses_weights() {
+        let source = |arg| {
+            let sources = sources(arg, false).unwrap();
+            assert_eq!(sources.len(), 1);
+            (sources[0].path.clone(), sources[0].weight)
+        };
+        assert_eq!(
+            source("list.txt@2.5"),
+            (PathBuf::from("list.txt"), Some(2.5))
+        );
+        assert_eq!(source("-@3"), (PathBuf::from("-"), Some(3.)));
+        // Without a valid weight, the "@" is part of the path.
+        assert_eq!(
+            source("list@home.txt"),
+            (PathBuf::from("list@home.txt"), None)
+        );
+        assert_eq!(source("list.txt@0"), (PathBuf::from("list.txt@0"), None));
+        assert_eq!(source("list.txt@-1"), (PathBuf::from("list.txt@-1"), None));
+    }
+}